@@ -1,7 +1,12 @@
 use std::error::Error;
+use std::fmt;
 
 use super::read_file;
 
+pub mod frequency;
+pub mod groups;
+pub mod repair;
+
 pub fn split_rucksack(line: &str) -> (String, String) {
     let length = line.len() / 2;
     let first = line.chars().take(length).collect::<String>();
@@ -24,6 +29,33 @@ pub fn evaluate_char_priority(c: char) -> i32 {
     }
 }
 
+/**
+a rucksack holding something that is not a letter, by its line index
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidItemError {
+    pub line: usize,
+    pub item: char,
+}
+
+impl fmt::Display for InvalidItemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {:?} is not an item", self.line + 1, self.item)
+    }
+}
+
+impl Error for InvalidItemError {}
+
+/**
+make sure every item in a rucksack is a letter, so priorities don't collide
+*/
+pub fn check_items(rucksack: &str, line: usize) -> Result<(), InvalidItemError> {
+    match rucksack.chars().find(|c| !c.is_ascii_alphabetic()) {
+        Some(item) => Err(InvalidItemError { line, item }),
+        None => Ok(()),
+    }
+}
+
 pub fn get_priority_sum(filename: &str) -> Result<i32, Box<dyn Error>> {
     let mut sum = 0;
 
@@ -0,0 +1,214 @@
+use std::error::Error;
+
+use super::super::read_file;
+use super::{check_items, evaluate_char_priority, InvalidItemError};
+
+/**
+how often one item type turns up across a file of rucksacks
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemFrequency {
+    pub item: char,
+    pub priority: i32,
+    // number of rucksacks holding at least one
+    pub rucksacks: usize,
+    // number of items of this type across every rucksack
+    pub occurrences: usize,
+    // the first rucksack it was seen in
+    pub first_line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrequencyReport {
+    pub rucksack_count: usize,
+    // sorted by priority
+    pub items: Vec<ItemFrequency>,
+}
+
+impl FrequencyReport {
+    /**
+    item types found in at least k rucksacks
+    */
+    pub fn in_at_least(&self, k: usize) -> Vec<char> {
+        self.items
+            .iter()
+            .filter(|f| f.rucksacks >= k)
+            .map(|f| f.item)
+            .collect()
+    }
+
+    /**
+    item types held by a single rucksack, with that rucksack's line index
+    */
+    pub fn unique(&self) -> Vec<(char, usize)> {
+        self.items
+            .iter()
+            .filter(|f| f.rucksacks == 1)
+            .map(|f| (f.item, f.first_line))
+            .collect()
+    }
+
+    /**
+    total number of items at each priority, for every priority from 1 to 52
+    */
+    pub fn priority_histogram(&self) -> Vec<(i32, usize)> {
+        (1..=52)
+            .map(|p| {
+                let count = self
+                    .items
+                    .iter()
+                    .find(|f| f.priority == p)
+                    .map_or(0, |f| f.occurrences);
+                (p, count)
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("item,priority,rucksacks,occurrences,first_line\n");
+        for f in &self.items {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                f.item, f.priority, f.rucksacks, f.occurrences, f.first_line
+            ));
+        }
+        csv
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut table = format!(
+            "{:<4} {:>8} {:>9} {:>11}\n",
+            "item", "priority", "rucksacks", "occurrences"
+        );
+        for f in &self.items {
+            table.push_str(&format!(
+                "{:<4} {:>8} {:>9} {:>11}\n",
+                f.item, f.priority, f.rucksacks, f.occurrences
+            ));
+        }
+        table
+    }
+}
+
+/**
+count every item type across the rucksacks, which must hold letters only
+*/
+pub fn analyse_frequencies(rucksacks: &[String]) -> Result<FrequencyReport, InvalidItemError> {
+    let mut items: Vec<ItemFrequency> = Vec::new();
+
+    for (line, rucksack) in rucksacks.iter().enumerate() {
+        check_items(rucksack, line)?;
+        let mut seen: Vec<char> = Vec::new();
+        for c in rucksack.chars() {
+            let idx = match items.iter().position(|f| f.item == c) {
+                Some(idx) => idx,
+                None => {
+                    items.push(ItemFrequency {
+                        item: c,
+                        priority: evaluate_char_priority(c),
+                        rucksacks: 0,
+                        occurrences: 0,
+                        first_line: line,
+                    });
+                    items.len() - 1
+                }
+            };
+            items[idx].occurrences += 1;
+            if !seen.contains(&c) {
+                seen.push(c);
+                items[idx].rucksacks += 1;
+            }
+        }
+    }
+
+    items.sort_by_key(|f| f.priority);
+
+    Ok(FrequencyReport {
+        rucksack_count: rucksacks.len(),
+        items,
+    })
+}
+
+pub fn get_frequency_report(filename: &str) -> Result<FrequencyReport, Box<dyn Error>> {
+    let lines = read_file(filename)?.collect::<Result<Vec<String>, _>>()?;
+    Ok(analyse_frequencies(&lines)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FrequencyReport {
+        let rucksacks = ["abA", "aab", "aZ"]
+            .iter()
+            .map(|&s| s.to_string())
+            .collect::<Vec<String>>();
+        analyse_frequencies(&rucksacks).unwrap()
+    }
+
+    #[test]
+    fn test_analyse_frequencies() {
+        let report = sample();
+        assert_eq!(report.rucksack_count, 3);
+        assert_eq!(
+            report.items[0],
+            ItemFrequency {
+                item: 'a',
+                priority: 1,
+                rucksacks: 3,
+                occurrences: 4,
+                first_line: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_analyse_frequencies_invalid_item() {
+        let rucksacks = ["1k".to_string(), "k".to_string()];
+        assert_eq!(
+            analyse_frequencies(&rucksacks),
+            Err(InvalidItemError { line: 0, item: '1' })
+        );
+    }
+
+    #[test]
+    fn test_in_at_least() {
+        let report = sample();
+        assert_eq!(report.in_at_least(2), vec!['a', 'b']);
+        assert_eq!(report.in_at_least(3), vec!['a']);
+    }
+
+    #[test]
+    fn test_unique() {
+        assert_eq!(sample().unique(), vec![('A', 0), ('Z', 2)]);
+    }
+
+    #[test]
+    fn test_priority_histogram() {
+        let histogram = sample().priority_histogram();
+        assert_eq!(histogram.len(), 52);
+        assert_eq!(histogram[0], (1, 4));
+        assert_eq!(histogram[51], (52, 1));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let csv = sample().to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("item,priority,rucksacks,occurrences,first_line")
+        );
+        assert_eq!(lines.next(), Some("a,1,3,4,0"));
+        assert_eq!(csv.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_get_frequency_report() {
+        let report = get_frequency_report("input/day3.test").unwrap();
+        assert_eq!(report.rucksack_count, 6);
+        // the two group badges turn up in at least three rucksacks
+        assert!(report.in_at_least(3).contains(&'r'));
+        assert!(report.in_at_least(3).contains(&'Z'));
+    }
+}
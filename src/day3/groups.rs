@@ -0,0 +1,303 @@
+use std::error::Error;
+
+use super::super::read_file;
+use super::{evaluate_char_priority, find_shared_char, InvalidItemError};
+
+/**
+a group of rucksacks (by line index) and the single badge they all carry
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub lines: Vec<usize>,
+    pub badge: char,
+}
+
+/**
+why a list of rucksacks cannot be split into badge groups
+
+`NotDivisible` and `Isolated` are certificates that can be checked on their own;
+`Exhausted` only records that the search found nothing after trying every
+combination, with no smaller structure to point at
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Infeasible {
+    // the number of rucksacks is not a multiple of the group size
+    NotDivisible { count: usize, k: usize },
+    // this rucksack shares exactly one item with no k - 1 others
+    Isolated { line: usize },
+    // every combination was tried and none covered the whole list
+    Exhausted { explored: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grouping {
+    Partitions(Vec<Vec<Group>>),
+    Infeasible(Infeasible),
+}
+
+/**
+bitmask of the item types in a rucksack, one bit per priority, or the first
+item that is not a letter
+*/
+pub fn item_mask(line: &str) -> Result<u64, char> {
+    line.chars()
+        .try_fold(0, |mask, c| match c.is_ascii_alphabetic() {
+            true => Ok(mask | 1 << evaluate_char_priority(c)),
+            false => Err(c),
+        })
+}
+
+struct Search<'a> {
+    masks: Vec<u64>,
+    rucksacks: &'a [String],
+    k: usize,
+    limit: usize,
+    assigned: Vec<bool>,
+    groups: Vec<Group>,
+    partitions: Vec<Vec<Group>>,
+    explored: usize,
+}
+
+impl Search<'_> {
+    fn done(&self) -> bool {
+        self.partitions.len() >= self.limit
+    }
+
+    fn partition(&mut self) {
+        if self.done() {
+            return;
+        }
+
+        // the lowest unassigned rucksack anchors the next group, so each
+        // partition is generated exactly once
+        let first = match self.assigned.iter().position(|&a| !a) {
+            Some(first) => first,
+            None => {
+                self.partitions.push(self.groups.clone());
+                return;
+            }
+        };
+
+        self.assigned[first] = true;
+        let mut members = vec![first];
+        self.extend(&mut members, first + 1, self.masks[first]);
+        self.assigned[first] = false;
+    }
+
+    fn extend(&mut self, members: &mut Vec<usize>, start: usize, shared: u64) {
+        self.explored += 1;
+
+        // shared items only shrink as the group grows
+        if shared == 0 || self.done() {
+            return;
+        }
+
+        if members.len() == self.k {
+            if shared.count_ones() == 1 {
+                let group = members
+                    .iter()
+                    .map(|&i| self.rucksacks[i].to_owned())
+                    .collect::<Vec<String>>();
+                self.groups.push(Group {
+                    lines: members.clone(),
+                    badge: find_shared_char(group),
+                });
+                self.partition();
+                self.groups.pop();
+            }
+            return;
+        }
+
+        for i in start..self.masks.len() {
+            if self.assigned[i] {
+                continue;
+            }
+            self.assigned[i] = true;
+            members.push(i);
+            self.extend(members, i + 1, shared & self.masks[i]);
+            members.pop();
+            self.assigned[i] = false;
+        }
+    }
+}
+
+/**
+check whether a rucksack can join any group of size k with exactly one shared item
+*/
+fn has_group(masks: &[u64], line: usize, k: usize) -> bool {
+    fn pick(masks: &[u64], line: usize, start: usize, left: usize, shared: u64) -> bool {
+        if shared == 0 {
+            return false;
+        }
+        if left == 0 {
+            return shared.count_ones() == 1;
+        }
+        (start..masks.len())
+            .filter(|&i| i != line)
+            .any(|i| pick(masks, line, i + 1, left - 1, shared & masks[i]))
+    }
+
+    pick(masks, line, 0, k - 1, masks[line])
+}
+
+/**
+split an unordered list of rucksacks into groups of k that each share exactly
+one item type, returning up to `limit` partitions or the reason none exist; an
+error if a rucksack holds something that is not a letter
+*/
+pub fn find_group_partitions(
+    rucksacks: &[String],
+    k: usize,
+    limit: usize,
+) -> Result<Grouping, InvalidItemError> {
+    let mut masks = Vec::with_capacity(rucksacks.len());
+    for (line, rucksack) in rucksacks.iter().enumerate() {
+        masks.push(item_mask(rucksack).map_err(|item| InvalidItemError { line, item })?);
+    }
+
+    if k == 0 || !rucksacks.len().is_multiple_of(k) {
+        return Ok(Grouping::Infeasible(Infeasible::NotDivisible {
+            count: rucksacks.len(),
+            k,
+        }));
+    }
+
+    let mut search = Search {
+        masks,
+        rucksacks,
+        k,
+        limit,
+        assigned: vec![false; rucksacks.len()],
+        groups: Vec::new(),
+        partitions: Vec::new(),
+        explored: 0,
+    };
+    search.partition();
+
+    if !search.partitions.is_empty() {
+        return Ok(Grouping::Partitions(search.partitions));
+    }
+
+    // prefer a single rucksack with no possible group as the certificate
+    let reason = match (0..rucksacks.len()).find(|&i| !has_group(&search.masks, i, k)) {
+        Some(line) => Infeasible::Isolated { line },
+        None => Infeasible::Exhausted {
+            explored: search.explored,
+        },
+    };
+    Ok(Grouping::Infeasible(reason))
+}
+
+pub fn get_group_partitions(
+    filename: &str,
+    k: usize,
+    limit: usize,
+) -> Result<Grouping, Box<dyn Error>> {
+    let lines = read_file(filename)?.collect::<Result<Vec<String>, _>>()?;
+    Ok(find_group_partitions(&lines, k, limit)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|&s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_get_group_partitions_shuffled() {
+        let grouping = get_group_partitions("input/day3_shuffled.test", 3, usize::MAX).unwrap();
+        // without ordering the sample splits two ways
+        let expected = vec![
+            vec![
+                Group {
+                    lines: vec![0, 2, 4],
+                    badge: 'Z',
+                },
+                Group {
+                    lines: vec![1, 3, 5],
+                    badge: 'r',
+                },
+            ],
+            vec![
+                Group {
+                    lines: vec![0, 3, 5],
+                    badge: 'q',
+                },
+                Group {
+                    lines: vec![1, 2, 4],
+                    badge: 'J',
+                },
+            ],
+        ];
+        assert_eq!(grouping, Grouping::Partitions(expected));
+    }
+
+    #[test]
+    fn test_find_group_partitions_all() {
+        // any pair of these shares exactly one item
+        let rucksacks = to_strings(&["ab", "ac", "ad", "ae"]);
+        match find_group_partitions(&rucksacks, 2, usize::MAX).unwrap() {
+            Grouping::Partitions(p) => assert_eq!(p.len(), 3),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_find_group_partitions_limit() {
+        let rucksacks = to_strings(&["ab", "ac", "ad", "ae"]);
+        match find_group_partitions(&rucksacks, 2, 1).unwrap() {
+            Grouping::Partitions(p) => assert_eq!(p.len(), 1),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_find_group_partitions_not_divisible() {
+        let rucksacks = to_strings(&["ab", "ac", "ad", "ae"]);
+        assert_eq!(
+            find_group_partitions(&rucksacks, 3, usize::MAX),
+            Ok(Grouping::Infeasible(Infeasible::NotDivisible {
+                count: 4,
+                k: 3
+            }))
+        );
+    }
+
+    #[test]
+    fn test_find_group_partitions_isolated() {
+        // "xy" shares nothing, "abc" and "abd" share two items
+        let rucksacks = to_strings(&["abc", "abd", "xy", "ae"]);
+        assert_eq!(
+            find_group_partitions(&rucksacks, 2, usize::MAX),
+            Ok(Grouping::Infeasible(Infeasible::Isolated { line: 2 }))
+        );
+    }
+
+    #[test]
+    fn test_find_group_partitions_exhausted() {
+        // every leaf can only pair with "abc", so no perfect matching exists
+        let rucksacks = to_strings(&["abc", "ax", "by", "cz"]);
+        assert!(matches!(
+            find_group_partitions(&rucksacks, 2, usize::MAX),
+            Ok(Grouping::Infeasible(Infeasible::Exhausted { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_item_mask() {
+        assert_eq!(item_mask("aA"), Ok(1 << 1 | 1 << 27));
+        assert_eq!(item_mask("a b"), Err(' '));
+        assert_eq!(item_mask("ab\r"), Err('\r'));
+    }
+
+    #[test]
+    fn test_find_group_partitions_invalid_item() {
+        let rucksacks = to_strings(&["ab", "a1"]);
+        assert_eq!(
+            find_group_partitions(&rucksacks, 2, usize::MAX),
+            Err(InvalidItemError { line: 1, item: '1' })
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::read_file;
+use super::{evaluate_char_priority, split_rucksack};
+
+/**
+the swaps needed to keep every item type in a single compartment of one rucksack
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairPlan {
+    pub line: usize,
+    // (item leaving the first compartment, item leaving the second)
+    pub swaps: Vec<(char, char)>,
+}
+
+impl RepairPlan {
+    pub fn effort(&self) -> usize {
+        self.swaps.len()
+    }
+}
+
+impl fmt::Display for RepairPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {} swaps", self.line + 1, self.effort())?;
+        for (out, back) in &self.swaps {
+            write!(f, " {}<->{}", out, back)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+why a line is not a rucksack that can be planned for
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairError {
+    // items are letters only
+    InvalidItem { line: usize, item: char },
+    // the two compartments have to hold the same number of items
+    OddLength { line: usize, length: usize },
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepairError::InvalidItem { line, item } => {
+                write!(f, "line {}: {:?} is not an item", line + 1, item)
+            }
+            RepairError::OddLength { line, length } => write!(
+                f,
+                "line {}: {} items cannot be split into two compartments",
+                line + 1,
+                length
+            ),
+        }
+    }
+}
+
+impl Error for RepairError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    // None where the compartments cannot be separated by swaps
+    pub plans: Vec<Option<RepairPlan>>,
+    pub total: usize,
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (line, plan) in self.plans.iter().enumerate() {
+            match plan {
+                Some(plan) => writeln!(f, "{}", plan)?,
+                None => writeln!(f, "line {}: cannot be repaired", line + 1)?,
+            }
+        }
+        write!(f, "total: {} swaps", self.total)
+    }
+}
+
+/**
+count each item type in a compartment, indexed by priority
+*/
+fn count_items(compartment: &str) -> [usize; 53] {
+    let mut counts = [0; 53];
+    for c in compartment.chars() {
+        counts[evaluate_char_priority(c) as usize] += 1;
+    }
+    counts
+}
+
+fn priority_char(priority: usize) -> char {
+    if priority <= 26 {
+        (b'a' + priority as u8 - 1) as char
+    } else {
+        (b'A' + priority as u8 - 27) as char
+    }
+}
+
+/**
+find the fewest swaps between compartments so no item type is in both
+
+every type ends up wholly in one compartment, so this picks the set of types
+that fill the first compartment exactly while leaving the fewest of its own
+items behind to be swapped out; none if no set of types fits, and an error
+if the line is not a rucksack
+*/
+pub fn plan_repair(line: &str, index: usize) -> Result<Option<RepairPlan>, RepairError> {
+    if let Some(item) = line.chars().find(|c| !c.is_ascii_alphabetic()) {
+        return Err(RepairError::InvalidItem { line: index, item });
+    }
+    if !line.len().is_multiple_of(2) {
+        return Err(RepairError::OddLength {
+            line: index,
+            length: line.len(),
+        });
+    }
+
+    let (first, second) = split_rucksack(line);
+    let (a, b) = (count_items(&first), count_items(&second));
+    let length = first.chars().count();
+
+    // best[s] = (swaps, types kept in the first compartment) filling s slots
+    let mut best: Vec<Option<(usize, u64)>> = vec![None; length + 1];
+    best[0] = Some((0, 0));
+
+    for t in 1..53 {
+        let total = a[t] + b[t];
+        if total == 0 {
+            continue;
+        }
+        let mut next = vec![None; length + 1];
+        for (s, entry) in best.iter().enumerate() {
+            let Some((cost, kept)) = *entry else {
+                continue;
+            };
+            // keep this type in the first compartment
+            if s + total <= length {
+                relax(&mut next[s + total], (cost, kept | 1 << t));
+            }
+            // move this type to the second compartment
+            relax(&mut next[s], (cost + a[t], kept));
+        }
+        best = next;
+    }
+
+    let Some((_, kept)) = best[length] else {
+        return Ok(None);
+    };
+
+    let outgoing = (1..53)
+        .filter(|t| kept & 1 << t == 0)
+        .flat_map(|t| std::iter::repeat_n(priority_char(t), a[t]));
+    let incoming = (1..53)
+        .filter(|t| kept & 1 << t != 0)
+        .flat_map(|t| std::iter::repeat_n(priority_char(t), b[t]));
+
+    Ok(Some(RepairPlan {
+        line: index,
+        swaps: outgoing.zip(incoming).collect(),
+    }))
+}
+
+fn relax(slot: &mut Option<(usize, u64)>, candidate: (usize, u64)) {
+    if slot.is_none_or(|(cost, _)| candidate.0 < cost) {
+        *slot = Some(candidate);
+    }
+}
+
+pub fn get_repair_report(filename: &str) -> Result<RepairReport, Box<dyn Error>> {
+    let mut plans = Vec::new();
+
+    for (i, line) in read_file(filename)?.enumerate() {
+        plans.push(plan_repair(&line?, i)?);
+    }
+
+    let total = plans.iter().flatten().map(|p| p.effort()).sum();
+    Ok(RepairReport { plans, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_repair_single_swap() {
+        let plan = plan_repair("abcd", 0).unwrap().unwrap();
+        assert_eq!(plan.effort(), 0);
+        // 'a' is in both halves, swapping it for 'd' separates them
+        let plan = plan_repair("abad", 0).unwrap().unwrap();
+        assert_eq!(plan.swaps, vec![('a', 'd')]);
+    }
+
+    #[test]
+    fn test_plan_repair_unrepairable() {
+        // three 'a's can never fit in a compartment of two
+        assert_eq!(plan_repair("aaab", 0), Ok(None));
+    }
+
+    #[test]
+    fn test_plan_repair_prefers_fewest_swaps() {
+        // keeping the 'a's on the left costs one swap, moving them costs two
+        let plan = plan_repair("aabcab", 0).unwrap().unwrap();
+        assert_eq!(plan.swaps, vec![('b', 'a')]);
+    }
+
+    #[test]
+    fn test_plan_repair_invalid() {
+        assert_eq!(
+            plan_repair("ab c", 0),
+            Err(RepairError::InvalidItem { line: 0, item: ' ' })
+        );
+        assert_eq!(
+            plan_repair("abc", 4),
+            Err(RepairError::OddLength { line: 4, length: 3 })
+        );
+        assert_eq!(
+            plan_repair("abc", 4).unwrap_err().to_string(),
+            "line 5: 3 items cannot be split into two compartments"
+        );
+    }
+
+    #[test]
+    fn test_get_repair_report() {
+        let report = get_repair_report("input/day3.test").unwrap();
+        assert_eq!(report.plans.len(), 6);
+        let efforts = report
+            .plans
+            .iter()
+            .map(|p| p.as_ref().map(|p| p.effort()))
+            .collect::<Vec<Option<usize>>>();
+        assert_eq!(
+            efforts,
+            vec![Some(1), Some(2), Some(1), Some(2), Some(2), Some(1)]
+        );
+        assert_eq!(report.total, 9);
+    }
+
+    #[test]
+    fn test_display_repair_plan() {
+        let plan = plan_repair("abad", 2).unwrap().unwrap();
+        assert_eq!(plan.to_string(), "line 3: 1 swaps a<->d");
+    }
+}
@@ -1,34 +1,64 @@
 use std::error::Error;
 
+use super::interval::{Interval, IntervalSet, ParseIntervalError, Relation};
 use super::read_file;
 
-pub fn parse_series(s: &str) -> Vec<i32> {
-    let bounds = s.split('-').collect::<Vec<&str>>();
+pub mod coverage;
+pub mod index;
+pub mod optimise;
 
-    // surely there's a better way to cast from &str to i32...
-    let bounds = bounds.iter().map(|&s| s.to_string()).collect::<Vec<String>>();
-    let bounds = bounds.iter().map(|s| s.parse::<i32>().unwrap().to_owned()).collect::<Vec<i32>>();
-    
-    (bounds[0]..bounds[1] + 1).collect::<Vec<i32>>()
+/**
+a range of sections, or none when its bounds are reversed as such a range
+holds no sections
+*/
+pub fn parse_series(s: &str) -> Result<Option<Interval<i32>>, ParseIntervalError> {
+    let (start, end) = Interval::parse_bounds(s)?;
+    Ok(Interval::try_new(start, end))
 }
 
-pub fn is_intersecting_set(line: &str) -> bool {
-    // split line into first and second
-    let line = line.split(',').collect::<Vec<&str>>();
-    // parse both series
-    let (first, second) = (parse_series(line[0]), parse_series(line[1]));
-    // check if one is contained in the other
-    first.iter().all(|i| second.contains(i)) || second.iter().all(|i| first.contains(i))
+/**
+every range on a line in the order written, empty ones included
+*/
+pub fn parse_ranges(line: &str) -> Result<Vec<Option<Interval<i32>>>, ParseIntervalError> {
+    line.split(',').map(parse_series).collect()
 }
 
-pub fn is_partial_intersecting_set(line: &str) -> bool {
-    // split line into first and second
-    let line = line.split(',').collect::<Vec<&str>>();
-    // parse both series
-    let (first, second) = (parse_series(line[0]), parse_series(line[1]));
-    // check if one is partially contained in the other
-    let test = first.iter().find(|&i| second.contains(i));
-    test.is_some()
+/**
+the ranges on a line that hold at least one section
+*/
+pub fn parse_line(line: &str) -> Result<Vec<Interval<i32>>, ParseIntervalError> {
+    Ok(parse_ranges(line)?.into_iter().flatten().collect())
+}
+
+/**
+every pair of ranges on a line, in the order they were written
+*/
+pub fn line_pairs<T: Copy>(ranges: &[T]) -> Vec<(T, T)> {
+    let mut pairs = Vec::new();
+    for (i, &a) in ranges.iter().enumerate() {
+        for &b in &ranges[i + 1..] {
+            pairs.push((a, b));
+        }
+    }
+    pairs
+}
+
+pub fn is_intersecting_set(line: &str) -> Result<bool, ParseIntervalError> {
+    let ranges = parse_ranges(line)?;
+    // check if one is contained in another, an empty range is inside any other
+    Ok(line_pairs(&ranges).iter().any(|pair| match pair {
+        (Some(a), Some(b)) => a.covers(b) || b.covers(a),
+        _ => true,
+    }))
+}
+
+pub fn is_partial_intersecting_set(line: &str) -> Result<bool, ParseIntervalError> {
+    let ranges = parse_ranges(line)?;
+    // check if one is partially contained in another, an empty range overlaps nothing
+    Ok(line_pairs(&ranges).iter().any(|pair| match pair {
+        (Some(a), Some(b)) => a.overlaps(b),
+        _ => false,
+    }))
 }
 
 pub fn get_intersecting_sum(filename: &str, allow_partial: bool) -> Result<i32, Box<dyn Error>> {
@@ -37,13 +67,60 @@ pub fn get_intersecting_sum(filename: &str, allow_partial: bool) -> Result<i32,
 
     for line in lines {
         let line = line?;
-        if (allow_partial && is_partial_intersecting_set(&line)) || is_intersecting_set(&line) {
+        if (allow_partial && is_partial_intersecting_set(&line)?) || is_intersecting_set(&line)? {
             sum += 1;
         }
     }
     Ok(sum)
 }
 
+pub fn parse_sections(filename: &str) -> Result<Vec<Interval<i32>>, Box<dyn Error>> {
+    let mut sections = Vec::new();
+
+    for line in read_file(filename)? {
+        sections.extend(parse_line(&line?)?);
+    }
+    Ok(sections)
+}
+
+/**
+sections between the lowest and highest assigned that nobody is cleaning
+*/
+pub fn get_unassigned_sections(filename: &str) -> Result<IntervalSet<i32>, Box<dyn Error>> {
+    let sections = parse_sections(filename)?;
+    Ok(sections.into_iter().collect::<IntervalSet<i32>>().gaps())
+}
+
+/**
+sections that every elf is cleaning
+*/
+pub fn get_shared_sections(filename: &str) -> Result<IntervalSet<i32>, Box<dyn Error>> {
+    let mut sections = parse_sections(filename)?.into_iter();
+
+    let first = match sections.next() {
+        Some(first) => IntervalSet::from(first),
+        None => return Ok(IntervalSet::new()),
+    };
+    Ok(sections.fold(first, |shared, s| {
+        shared.intersection(&IntervalSet::from(s))
+    }))
+}
+
+/**
+how often each of Allen's relations holds between ranges sharing a line
+*/
+pub fn get_relation_counts(filename: &str) -> Result<Vec<(Relation, usize)>, Box<dyn Error>> {
+    let mut counts = Relation::ALL.map(|r| (r, 0));
+
+    for line in read_file(filename)? {
+        let ranges = parse_line(&line?)?;
+        for (a, b) in line_pairs(&ranges) {
+            counts[a.relation(&b) as usize].1 += 1;
+        }
+    }
+    Ok(counts.to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,12 +132,12 @@ mod tests {
 
     #[test]
     fn test_is_partial_intersecting_set() {
-        assert!(is_partial_intersecting_set("2-8,6-9"))
+        assert!(is_partial_intersecting_set("2-8,6-9").unwrap())
     }
 
     #[test]
     fn test_is_not_partial_intersecting_set() {
-        assert!(!is_partial_intersecting_set("2-4,6-8"))
+        assert!(!is_partial_intersecting_set("2-4,6-8").unwrap())
     }
 
     #[test]
@@ -70,17 +147,109 @@ mod tests {
 
     #[test]
     fn test_is_intersecting_set() {
-        assert!(is_intersecting_set("2-8,3-7"))
+        assert!(is_intersecting_set("2-8,3-7").unwrap())
     }
 
     #[test]
     fn test_is_not_intersecting_set() {
-        assert!(!is_intersecting_set("2-4,6-8"))
+        assert!(!is_intersecting_set("2-4,6-8").unwrap())
     }
 
     #[test]
     fn test_parse_series() {
-        assert_eq!(parse_series("2-4"), vec!(2, 3, 4));
+        assert_eq!(parse_series("2-4"), Ok(Some(Interval::new(2, 4))));
+        assert_eq!(parse_series("4-2"), Ok(None));
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        assert!(parse_series("").is_err());
+        assert!(parse_line("2-4,6").is_err());
+        assert!(is_intersecting_set("2-4,x-8").is_err());
+        assert!(get_intersecting_sum("input/day4_malformed.test", false).is_err());
+    }
+
+    #[test]
+    fn test_reversed_range() {
+        // a reversed range is empty, so it is contained but overlaps nothing
+        assert!(is_intersecting_set("4-2,3-3").unwrap());
+        assert!(!is_partial_intersecting_set("4-2,3-3").unwrap());
+        assert_eq!(parse_line("4-2,3-3"), Ok(vec![Interval::new(3, 3)]));
+    }
+
+    #[test]
+    fn test_is_intersecting_set_large() {
+        assert!(is_intersecting_set("1-1000000000,5-999999999").unwrap());
+        assert!(is_partial_intersecting_set("1-1000000000,999999999-2000000000").unwrap());
+    }
+
+    #[test]
+    fn test_get_unassigned_sections() {
+        // 2-9 are all assigned on the test input
+        assert!(get_unassigned_sections("input/day4.test")
+            .unwrap()
+            .is_empty());
+        let unassigned = get_unassigned_sections("input/day4_coverage.test").unwrap();
+        assert_eq!(unassigned.to_string(), "6-6,13-19");
+    }
+
+    #[test]
+    fn test_get_shared_sections() {
+        assert!(get_shared_sections("input/day4.test").unwrap().is_empty());
+        let shared = get_shared_sections("input/day4_shared.test").unwrap();
+        assert_eq!(shared.to_string(), "3-4");
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("2-4,6-8,1-1"),
+            Ok(vec![
+                Interval::new(2, 4),
+                Interval::new(6, 8),
+                Interval::new(1, 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_is_intersecting_set_n_way() {
+        assert!(is_intersecting_set("1-2,5-9,6-7").unwrap());
+        assert!(!is_intersecting_set("1-2,5-9,8-10").unwrap());
+        assert!(is_partial_intersecting_set("1-2,5-9,8-10").unwrap());
+        assert!(!is_partial_intersecting_set("1-2,3-4,5-6").unwrap());
+    }
+
+    #[test]
+    fn test_get_intersecting_sum_n_way() {
+        assert_eq!(
+            get_intersecting_sum("input/day4_nway.test", false).unwrap(),
+            2
+        );
+        assert_eq!(
+            get_intersecting_sum("input/day4_nway.test", true).unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_get_relation_counts() {
+        let counts = get_relation_counts("input/day4.test").unwrap();
+        let count = |r: Relation| counts.iter().find(|(c, _)| *c == r).unwrap().1;
+        assert_eq!(counts.len(), 13);
+        assert_eq!(count(Relation::Before), 1);
+        assert_eq!(count(Relation::Meets), 1);
+        assert_eq!(count(Relation::Overlaps), 2);
+        assert_eq!(count(Relation::Contains), 1);
+        assert_eq!(count(Relation::Finishes), 1);
+        assert_eq!(counts.iter().map(|(_, n)| n).sum::<usize>(), 6);
+    }
+
+    #[test]
+    fn test_get_relation_counts_n_way() {
+        let counts = get_relation_counts("input/day4_nway.test").unwrap();
+        // three ranges per line gives three pairs
+        assert_eq!(counts.iter().map(|(_, n)| n).sum::<usize>(), 12);
     }
 
     #[test]
@@ -88,4 +257,4 @@ mod tests {
         let lines = read_file("input/day4.test").unwrap();
         assert_eq!(lines.count(), 6);
     }
-}
\ No newline at end of file
+}
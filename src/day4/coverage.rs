@@ -0,0 +1,147 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::interval::{Interval, IntervalSet};
+use super::parse_sections;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    // most elves assigned to any one section
+    pub max_depth: usize,
+    // the sections where that happens
+    pub max_at: IntervalSet<i32>,
+    pub covered_once: IntervalSet<i32>,
+    // histogram[d] is the number of sections, between the lowest and highest
+    // assigned, that exactly d elves are cleaning
+    pub histogram: Vec<u64>,
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "max coverage: {} at {}", self.max_depth, self.max_at)?;
+        writeln!(f, "covered once: {}", self.covered_once)?;
+        write!(f, "depth sections")?;
+        for (depth, count) in self.histogram.iter().enumerate() {
+            write!(f, "\n{:>5} {:>8}", depth, count)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+sweep every range at once, tracking how many are open between each pair of
+consecutive endpoints
+*/
+pub fn sweep_coverage(sections: &[Interval<i32>]) -> CoverageReport {
+    // widen so the event after an i32::MAX end doesn't overflow
+    let mut events = sections
+        .iter()
+        .flat_map(|s| [(s.start as i64, 1), (s.end as i64 + 1, -1)])
+        .collect::<Vec<(i64, i64)>>();
+    events.sort();
+
+    let mut report = CoverageReport {
+        max_depth: 0,
+        max_at: IntervalSet::new(),
+        covered_once: IntervalSet::new(),
+        histogram: vec![0],
+    };
+    let mut max_at = Vec::new();
+    let mut covered_once = Vec::new();
+    let mut depth: i64 = 0;
+
+    for (i, &(at, change)) in events.iter().enumerate() {
+        depth += change;
+
+        // the depth holds from here up to the next event
+        let next = match events.get(i + 1) {
+            Some(&(next, _)) if next > at => next,
+            _ => continue,
+        };
+        let run = Interval::new(at as i32, (next - 1) as i32);
+        let d = depth as usize;
+
+        if d >= report.histogram.len() {
+            report.histogram.resize(d + 1, 0);
+        }
+        report.histogram[d] += (next - at) as u64;
+
+        if d == 1 {
+            covered_once.push(run);
+        }
+        if d > report.max_depth {
+            report.max_depth = d;
+            max_at.clear();
+        }
+        if d == report.max_depth && d > 0 {
+            max_at.push(run);
+        }
+    }
+
+    report.max_at = max_at.into_iter().collect();
+    report.covered_once = covered_once.into_iter().collect();
+    report
+}
+
+pub fn get_coverage_report(filename: &str) -> Result<CoverageReport, Box<dyn Error>> {
+    Ok(sweep_coverage(&parse_sections(filename)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_coverage_report() {
+        let report = get_coverage_report("input/day4.test").unwrap();
+        assert_eq!(report.max_depth, 8);
+        assert_eq!(report.max_at.to_string(), "6-6");
+        assert_eq!(report.covered_once.to_string(), "9-9");
+        assert_eq!(report.histogram, vec![0, 1, 0, 0, 2, 1, 1, 2, 1]);
+    }
+
+    #[test]
+    fn test_sweep_coverage_many_runs() {
+        // 40k disjoint ranges each covered once
+        let sections = (0..40_000)
+            .map(|i| Interval::new(3 * i, 3 * i + 1))
+            .collect::<Vec<Interval<i32>>>();
+        let report = sweep_coverage(&sections);
+        assert_eq!(report.covered_once.iter().count(), 40_000);
+        assert_eq!(report.histogram[1], 80_000);
+    }
+
+    #[test]
+    fn test_sweep_coverage_gaps() {
+        let sections = [
+            Interval::new(1, 2),
+            Interval::new(5, 6),
+            Interval::new(6, 8),
+        ];
+        let report = sweep_coverage(&sections);
+        assert_eq!(report.max_depth, 2);
+        assert_eq!(report.max_at.to_string(), "6-6");
+        assert_eq!(report.covered_once.to_string(), "1-2,5-5,7-8");
+        assert_eq!(report.histogram, vec![2, 5, 1]);
+    }
+
+    #[test]
+    fn test_sweep_coverage_extremes() {
+        let sections = [
+            Interval::new(i32::MAX - 1, i32::MAX),
+            Interval::new(i32::MAX, i32::MAX),
+        ];
+        let report = sweep_coverage(&sections);
+        assert_eq!(
+            report.max_at,
+            IntervalSet::from(Interval::new(i32::MAX, i32::MAX))
+        );
+    }
+
+    #[test]
+    fn test_sweep_coverage_empty() {
+        let report = sweep_coverage(&[]);
+        assert_eq!(report.max_depth, 0);
+        assert!(report.max_at.is_empty());
+    }
+}
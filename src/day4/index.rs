@@ -0,0 +1,206 @@
+use std::error::Error;
+
+use super::super::interval::{Interval, ParseIntervalError};
+use super::super::read_file;
+use super::parse_ranges;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hit {
+    pub line: usize,
+    // which of the comma separated ranges on the line, counted from zero
+    pub range: usize,
+    pub sections: Interval<i32>,
+}
+
+/**
+static interval tree over every range in an assignment file
+
+ranges are sorted by start and a max-end tree is kept over them, so a query
+only walks the branches that can still reach it: O(log n) per reported hit
+*/
+#[derive(Debug, Clone)]
+pub struct SectionIndex {
+    hits: Vec<Hit>,
+    max_end: Vec<i32>,
+}
+
+impl SectionIndex {
+    pub fn new(mut hits: Vec<Hit>) -> Self {
+        hits.sort_by_key(|h| h.sections.start);
+
+        let mut index = SectionIndex {
+            max_end: vec![i32::MIN; 4 * hits.len().max(1)],
+            hits,
+        };
+        if !index.hits.is_empty() {
+            index.build(1, 0, index.hits.len());
+        }
+        index
+    }
+
+    fn build(&mut self, node: usize, lo: usize, hi: usize) -> i32 {
+        let end = if hi - lo == 1 {
+            self.hits[lo].sections.end
+        } else {
+            let mid = (lo + hi) / 2;
+            self.build(2 * node, lo, mid)
+                .max(self.build(2 * node + 1, mid, hi))
+        };
+        self.max_end[node] = end;
+        end
+    }
+
+    pub fn from_lines<S: AsRef<str>>(lines: &[S]) -> Result<Self, ParseIntervalError> {
+        let mut hits = Vec::with_capacity(lines.len() * 2);
+        for (line, s) in lines.iter().enumerate() {
+            // empty ranges hold no sections but still count towards positions
+            for (range, sections) in parse_ranges(s.as_ref())?.into_iter().enumerate() {
+                if let Some(sections) = sections {
+                    hits.push(Hit {
+                        line,
+                        range,
+                        sections,
+                    });
+                }
+            }
+        }
+        Ok(Self::new(hits))
+    }
+
+    pub fn from_file(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let lines = read_file(filename)?.collect::<Result<Vec<String>, _>>()?;
+        Ok(Self::from_lines(&lines)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    /**
+    every range that includes the given section, ordered by line then position on it
+    */
+    pub fn stab(&self, section: i32) -> Vec<Hit> {
+        self.overlapping(Interval::new(section, section))
+    }
+
+    /**
+    every range that shares at least one section with the query
+    */
+    pub fn overlapping(&self, query: Interval<i32>) -> Vec<Hit> {
+        let mut found = Vec::new();
+
+        // only ranges starting at or before the query end can overlap it
+        let prefix = self.hits.partition_point(|h| h.sections.start <= query.end);
+        if prefix > 0 {
+            self.collect(1, 0, self.hits.len(), prefix, query.start, &mut found);
+        }
+
+        found.sort_by_key(|h| (h.line, h.range));
+        found
+    }
+
+    fn collect(
+        &self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        prefix: usize,
+        start: i32,
+        found: &mut Vec<Hit>,
+    ) {
+        if lo >= prefix || self.max_end[node] < start {
+            return;
+        }
+        if hi - lo == 1 {
+            found.push(self.hits[lo]);
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        self.collect(2 * node, lo, mid, prefix, start, found);
+        self.collect(2 * node + 1, mid, hi, prefix, start, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_line;
+    use super::*;
+
+    fn sample() -> SectionIndex {
+        SectionIndex::from_file("input/day4.test").unwrap()
+    }
+
+    #[test]
+    fn test_stab() {
+        let lines = sample()
+            .stab(7)
+            .iter()
+            .map(|h| (h.line, h.range))
+            .collect::<Vec<(usize, usize)>>();
+        assert_eq!(lines, vec![(0, 1), (2, 0), (2, 1), (3, 0), (3, 1), (5, 1)]);
+    }
+
+    #[test]
+    fn test_stab_outside() {
+        assert!(sample().stab(1).is_empty());
+        assert!(sample().stab(10).is_empty());
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let hits = sample().overlapping(Interval::new(1, 2));
+        assert_eq!(hits.len(), 4);
+        assert!(hits.iter().all(|h| h.sections.start == 2));
+    }
+
+    #[test]
+    fn test_overlapping_matches_scan() {
+        let index = sample();
+        let lines = read_file("input/day4.test")
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        for section in 0..11 {
+            let expected = lines
+                .iter()
+                .map(|l| parse_line(l).unwrap())
+                .filter(|ranges| ranges.iter().any(|r| r.contains(section)))
+                .count();
+            let found = index.stab(section);
+            let mut found_lines = found.iter().map(|h| h.line).collect::<Vec<usize>>();
+            found_lines.dedup();
+            assert_eq!(found_lines.len(), expected);
+        }
+    }
+
+    #[test]
+    fn test_n_way_lines() {
+        let index = SectionIndex::from_lines(&["1-2,3-4,50-60", "7-9"]).unwrap();
+        assert_eq!(index.len(), 4);
+        let hits = index.stab(55);
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].line, hits[0].range), (0, 2));
+        assert_eq!(index.stab(8)[0].line, 1);
+
+        let index = SectionIndex::from_lines(&["5-1,3-4"]).unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.stab(3)[0].range, 1);
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        assert!(SectionIndex::from_lines(&["2-4,6-8", ""]).is_err());
+        assert!(SectionIndex::from_lines(&["2-4,6-"]).is_err());
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let index = SectionIndex::from_lines::<&str>(&[]).unwrap();
+        assert!(index.is_empty());
+        assert!(index.stab(3).is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::interval::{Interval, IntervalSet};
+use super::super::read_file;
+use super::coverage::sweep_coverage;
+use super::parse_line;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    // a range has to be at least one section long
+    ZeroBudget,
+    // with no required sections there is nothing to plan the ranges around
+    NothingRequired,
+    // this many ranges of the budgeted length are needed to cover everything
+    NotEnoughElves { needed: usize, elves: usize },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::ZeroBudget => write!(f, "budget must allow at least one section"),
+            PlanError::NothingRequired => write!(f, "no sections are required"),
+            PlanError::NotEnoughElves { needed, elves } => write!(
+                f,
+                "covering every section needs {} elves but only {} are assigned",
+                needed, elves
+            ),
+        }
+    }
+}
+
+impl Error for PlanError {}
+
+/**
+new assignments with the same number of ranges per line as the original file
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignmentPlan {
+    pub lines: Vec<Vec<Interval<i32>>>,
+    // redundant overlap before and after replanning
+    pub redundant_before: u64,
+    pub redundant_after: u64,
+}
+
+impl fmt::Display for AssignmentPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            let ranges = line.iter().map(|r| r.to_string()).collect::<Vec<String>>();
+            writeln!(f, "{}", ranges.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/**
+sum over every section of how many elves beyond the first are cleaning it
+*/
+pub fn redundant_overlap(ranges: &[Interval<i32>]) -> u64 {
+    sweep_coverage(ranges)
+        .histogram
+        .iter()
+        .enumerate()
+        .skip(2)
+        .map(|(depth, &count)| (depth as u64 - 1) * count)
+        .sum()
+}
+
+/**
+fewest ranges of at most `budget` sections that cover every required section,
+each trimmed to start and end on a required section
+*/
+fn cover(required: &IntervalSet<i32>, budget: i32) -> Vec<Interval<i32>> {
+    let mut chunks = Vec::new();
+    let mut intervals = required.iter().copied();
+    let mut current = intervals.next();
+
+    while let Some(interval) = current {
+        let start = interval.start;
+        let limit = start.saturating_add(budget - 1);
+        let mut end = interval.end.min(limit);
+
+        // pull in whole or partial intervals until the budget runs out
+        current = if end < interval.end {
+            Some(Interval::new(end + 1, interval.end))
+        } else {
+            loop {
+                match intervals.next() {
+                    Some(next) if next.start <= limit => {
+                        end = next.end.min(limit);
+                        if end < next.end {
+                            break Some(Interval::new(end + 1, next.end));
+                        }
+                    }
+                    next => break next,
+                }
+            }
+        };
+
+        chunks.push(Interval::new(start, end));
+    }
+
+    chunks
+}
+
+/**
+the nth required section counting from zero, which must exist
+*/
+fn nth_section(required: &IntervalSet<i32>, mut n: u128) -> i32 {
+    for interval in required.iter() {
+        if n < interval.len() {
+            return (interval.start as i64 + n as i64) as i32;
+        }
+        n -= interval.len();
+    }
+    panic!("section is past the end of the required set");
+}
+
+/**
+split a range into two, each holding about half of its required sections
+*/
+fn split(
+    chunk: Interval<i32>,
+    required: &IntervalSet<i32>,
+) -> Option<(Interval<i32>, Interval<i32>)> {
+    let inside = required.intersection(&IntervalSet::from(chunk));
+    let count = inside.len();
+    if count < 2 {
+        return None;
+    }
+
+    // the left half ends on a required section and the right half starts on one
+    let left_end = nth_section(&inside, count / 2 - 1);
+    let right_start = nth_section(&inside, count / 2);
+    Some((
+        Interval::new(chunk.start, left_end),
+        Interval::new(right_start, chunk.end),
+    ))
+}
+
+/**
+reassign every elf a contiguous range of at most `budget` sections so that all
+required sections are covered with as little redundant overlap as possible
+
+the fewest budget sized ranges are laid over the required sections, then split
+until every elf has one; if there are more elves than required sections the
+rest double up on single sections, which is the least overlap they can add
+*/
+pub fn plan_assignments(
+    lines: &[Vec<Interval<i32>>],
+    required: &IntervalSet<i32>,
+    budget: i32,
+) -> Result<AssignmentPlan, PlanError> {
+    if budget < 1 {
+        return Err(PlanError::ZeroBudget);
+    }
+    if required.is_empty() {
+        return Err(PlanError::NothingRequired);
+    }
+
+    let elves = lines
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<Interval<i32>>>();
+    let mut chunks = cover(required, budget);
+    if chunks.len() > elves.len() {
+        return Err(PlanError::NotEnoughElves {
+            needed: chunks.len(),
+            elves: elves.len(),
+        });
+    }
+
+    // split the busiest ranges until every elf has work of their own
+    while chunks.len() < elves.len() {
+        let busiest = (0..chunks.len())
+            .max_by_key(|&i| required.intersection(&IntervalSet::from(chunks[i])).len());
+        match busiest.and_then(|i| split(chunks[i], required).map(|halves| (i, halves))) {
+            Some((i, (left, right))) => {
+                chunks[i] = left;
+                chunks.insert(i + 1, right);
+            }
+            None => break,
+        }
+    }
+
+    // anyone left over shares a single section
+    let mut extra = 0;
+    while chunks.len() < elves.len() {
+        let section = nth_section(required, extra % required.len());
+        chunks.push(Interval::new(section, section));
+        extra += 1;
+    }
+    chunks.sort();
+
+    // hand the ranges out in the order the elves already sit, so nobody moves far
+    let mut order = (0..elves.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&i| (elves[i].start, i));
+    let mut assigned = elves.clone();
+    for (&elf, &chunk) in order.iter().zip(chunks.iter()) {
+        assigned[elf] = chunk;
+    }
+
+    let mut next = assigned.iter();
+    let planned = lines
+        .iter()
+        .map(|line| line.iter().map(|_| *next.next().unwrap()).collect())
+        .collect();
+
+    Ok(AssignmentPlan {
+        lines: planned,
+        redundant_before: redundant_overlap(&elves),
+        redundant_after: redundant_overlap(&assigned),
+    })
+}
+
+pub fn get_assignment_plan(
+    filename: &str,
+    required: &IntervalSet<i32>,
+    budget: i32,
+) -> Result<AssignmentPlan, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for line in read_file(filename)? {
+        lines.push(parse_line(&line?)?);
+    }
+    Ok(plan_assignments(&lines, required, budget)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required(a: i32, b: i32) -> IntervalSet<i32> {
+        IntervalSet::from(Interval::new(a, b))
+    }
+
+    #[test]
+    fn test_redundant_overlap() {
+        let ranges = [
+            Interval::new(1, 4),
+            Interval::new(3, 5),
+            Interval::new(4, 4),
+        ];
+        assert_eq!(redundant_overlap(&ranges), 3);
+    }
+
+    #[test]
+    fn test_get_assignment_plan() {
+        let plan = get_assignment_plan("input/day4.test", &required(2, 9), 3).unwrap();
+        assert_eq!(plan.redundant_before, 34);
+        assert_eq!(plan.redundant_after, 4);
+        assert_eq!(
+            plan.to_string(),
+            "2-2,7-7\n2-2,4-4\n6-6,9-9\n3-3,4-4\n8-8,5-5\n3-3,5-5\n"
+        );
+    }
+
+    #[test]
+    fn test_plan_assignments_covers_required() {
+        let lines = vec![
+            vec![Interval::new(1, 2), Interval::new(1, 9)],
+            vec![Interval::new(30, 40), Interval::new(2, 3)],
+        ];
+        let needed = [Interval::new(1, 10), Interval::new(20, 21)]
+            .into_iter()
+            .collect::<IntervalSet<i32>>();
+        let plan = plan_assignments(&lines, &needed, 6).unwrap();
+
+        let ranges = plan.lines.concat();
+        assert!(ranges.iter().all(|r| r.len() <= 6));
+        let covered = ranges.iter().copied().collect::<IntervalSet<i32>>();
+        assert!(needed.difference(&covered).is_empty());
+        assert_eq!(plan.redundant_after, 0);
+        assert_eq!(plan.to_string(), "1-3,4-6\n20-21,7-10\n");
+    }
+
+    #[test]
+    fn test_plan_assignments_not_enough_elves() {
+        let lines = vec![vec![Interval::new(1, 2), Interval::new(3, 4)]];
+        assert_eq!(
+            plan_assignments(&lines, &required(1, 10), 4),
+            Err(PlanError::NotEnoughElves {
+                needed: 3,
+                elves: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_plan_assignments_zero_budget() {
+        let lines = vec![vec![Interval::new(1, 2)]];
+        assert_eq!(
+            plan_assignments(&lines, &required(1, 2), 0),
+            Err(PlanError::ZeroBudget)
+        );
+    }
+
+    #[test]
+    fn test_plan_assignments_nothing_required() {
+        let lines = vec![vec![Interval::new(1, 100), Interval::new(1, 100)]];
+        assert_eq!(
+            plan_assignments(&lines, &IntervalSet::new(), 3),
+            Err(PlanError::NothingRequired)
+        );
+    }
+
+    #[test]
+    fn test_plan_assignments_within_budget() {
+        let lines = vec![
+            vec![Interval::new(1, 100), Interval::new(1, 100)],
+            vec![Interval::new(5, 60), Interval::new(40, 90)],
+        ];
+        for budget in 1..=12 {
+            for needed in [required(1, 1), required(10, 20), required(3, 40)] {
+                if let Ok(plan) = plan_assignments(&lines, &needed, budget) {
+                    assert!(plan
+                        .lines
+                        .concat()
+                        .iter()
+                        .all(|r| r.len() <= budget as u128));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cover_spans_gaps() {
+        let needed = [
+            Interval::new(1, 2),
+            Interval::new(4, 4),
+            Interval::new(9, 12),
+        ]
+        .into_iter()
+        .collect::<IntervalSet<i32>>();
+        assert_eq!(
+            cover(&needed, 5),
+            vec![Interval::new(1, 4), Interval::new(9, 12)]
+        );
+        assert_eq!(
+            cover(&needed, 2),
+            vec![
+                Interval::new(1, 2),
+                Interval::new(4, 4),
+                Interval::new(9, 10),
+                Interval::new(11, 12)
+            ]
+        );
+    }
+}
@@ -1,141 +1,577 @@
 use std::{
-    fs::File,
-    io::{BufReader, Lines}, error::Error,
+    error::Error,
+    fmt,
+    fs::{write, File},
+    io::{BufReader, Lines},
 };
 
 use super::read_file;
 
-pub fn parse_state(mut layers: Vec<String>) -> Vec<String> {
-    let mut initial_state: Vec<String> = Vec::new();
+pub mod optimise;
+pub mod planner;
+pub mod provenance;
+pub mod rope;
+pub mod schedule;
+pub mod sim;
+pub mod validate;
 
-    // flip the layers to push onto the vector in the right order
-    layers.reverse();
+use validate::{check_move_by, InstructionError, MoveError};
 
-    // get number of stacks
-    let stack_ct = (layers[0].len() + 1) / 4;
+type Instructions = Vec<[usize; 3]>;
 
-    // go through stacks and push to initial state vector as Strings
-    for i in 0..stack_ct {
-        initial_state.push(
-            layers
+/**
+the crates on each stack, listed bottom first
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Stacks {
+    pub stacks: Vec<Vec<String>>,
+}
+
+impl Stacks {
+    /**
+    build stacks from strings of single character crate labels, bottom first
+    */
+    pub fn from_labels(stacks: &[&str]) -> Self {
+        Stacks {
+            stacks: stacks
                 .iter()
-                .map(|s| s.chars().nth(i * 4 + 1).unwrap())
-                .collect::<String>()
-                .trim()
-                .to_string(),
-        );
+                .map(|s| s.chars().map(|c| c.to_string()).collect())
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    /**
+    the label on top of every stack, skipping empty stacks
+    */
+    pub fn top_crates(&self) -> String {
+        self.stacks
+            .iter()
+            .filter_map(|s| s.last())
+            .map(|s| s.as_str())
+            .collect::<String>()
+    }
+
+    /**
+    draw the stacks the way the puzzle input does, numbering row last
+
+    every column is as wide as the widest crate or stack number, and every
+    line is padded to the full width so `parse_state` reads it straight back
+    */
+    pub fn render(&self) -> Vec<String> {
+        let label_width = self
+            .stacks
+            .iter()
+            .flatten()
+            .map(|l| l.chars().count() + 2)
+            .max()
+            .unwrap_or(3);
+        let width = label_width.max(self.len().to_string().len()).max(3);
+        let height = self.stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+
+        let mut lines = Vec::with_capacity(height + 1);
+        for layer in (0..height).rev() {
+            let cells = self
+                .stacks
+                .iter()
+                .map(|s| match s.get(layer) {
+                    Some(label) => format!("{:<width$}", format!("[{}]", label)),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<String>>();
+            lines.push(cells.join(" "));
+        }
+
+        let numbers = (1..=self.len())
+            .map(|n| {
+                let n = n.to_string();
+                // centre the number, leaning right when it can't be exact
+                let left = (width - n.len()).div_ceil(2);
+                format!("{}{:<w$}", " ".repeat(left), n, w = width - left)
+            })
+            .collect::<Vec<String>>();
+        lines.push(numbers.join(" "));
+
+        lines
+    }
+}
+
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render().join("\n"))
+    }
+}
+
+/**
+dump a stack drawing to a file, e.g. to compare intermediate states
+*/
+pub fn write_state(filename: &str, state: &Stacks) -> Result<(), Box<dyn Error>> {
+    write(filename, format!("{}\n", state))?;
+    Ok(())
+}
+
+pub fn read_state(filename: &str) -> Result<Stacks, Box<dyn Error>> {
+    let drawing = read_file(filename)?
+        .collect::<Result<Vec<String>, _>>()?
+        .into_iter()
+        .take_while(|l| !l.trim().is_empty())
+        .collect();
+    Ok(parse_state(drawing)?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseStateError {
+    // the drawing has no ` 1   2   3 ` row under the crates
+    MissingNumbering,
+    // the numbering row doesn't count up from 1
+    BadNumbering { found: String },
+    // rows are counted from the top of the drawing, columns in chars
+    UnclosedCrate { row: usize, column: usize },
+    Unaligned { row: usize, column: usize },
+    Floating { row: usize, stack: usize },
+}
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseStateError::MissingNumbering => write!(f, "stack drawing has no numbering row"),
+            ParseStateError::BadNumbering { found } => {
+                write!(
+                    f,
+                    "stack numbering should count up from 1, found '{}'",
+                    found
+                )
+            }
+            ParseStateError::UnclosedCrate { row, column } => {
+                write!(
+                    f,
+                    "crate at row {} column {} has no closing ']'",
+                    row + 1,
+                    column + 1
+                )
+            }
+            ParseStateError::Unaligned { row, column } => write!(
+                f,
+                "crate at row {} column {} is not above any stack number",
+                row + 1,
+                column + 1
+            ),
+            ParseStateError::Floating { row, stack } => {
+                write!(
+                    f,
+                    "crate at row {} on stack {} has nothing under it",
+                    row + 1,
+                    stack
+                )
+            }
+        }
+    }
+}
+
+impl Error for ParseStateError {}
+
+/**
+find each run of non-whitespace in a line, as (first char, last char, text)
+*/
+fn tokens(line: &str) -> Vec<(usize, usize, String)> {
+    let mut tokens: Vec<(usize, usize, String)> = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (i, c) in line.chars().chain(std::iter::once(' ')).enumerate() {
+        match (&mut current, c.is_whitespace()) {
+            (Some((_, text)), false) => text.push(c),
+            (None, false) => current = Some((i, c.to_string())),
+            (Some(_), true) => {
+                let (start, text) = current.take().unwrap();
+                tokens.push((start, i - 1, text));
+            }
+            (None, true) => {}
+        }
+    }
+    tokens
+}
+
+/**
+parse a stack drawing, the last line being the stack numbers
+
+each crate belongs to the stack whose number sits under it, so any number of
+stacks, wider labels and trimmed lines are all fine
+*/
+pub fn parse_state(mut drawing: Vec<String>) -> Result<Stacks, ParseStateError> {
+    let numbering = drawing.pop().ok_or(ParseStateError::MissingNumbering)?;
+    let numbers = tokens(&numbering);
+
+    if numbers.is_empty() || numbers.iter().any(|(_, _, n)| n.parse::<usize>().is_err()) {
+        return Err(ParseStateError::MissingNumbering);
+    }
+    for (i, (_, _, n)) in numbers.iter().enumerate() {
+        if n.parse::<usize>() != Ok(i + 1) {
+            return Err(ParseStateError::BadNumbering {
+                found: numbering.trim().to_string(),
+            });
+        }
+    }
+
+    let mut stacks = vec![Vec::new(); numbers.len()];
+
+    // work up from the bottom layer so each crate lands on the one below it
+    for (layer, line) in drawing.iter().rev().enumerate() {
+        let row = drawing.len() - 1 - layer;
+        let chars = line.chars().collect::<Vec<char>>();
+        let mut i = 0;
+
+        while i < chars.len() {
+            if chars[i] != '[' {
+                i += 1;
+                continue;
+            }
+            let close = (i + 1..chars.len())
+                .find(|&j| chars[j] == ']')
+                .ok_or(ParseStateError::UnclosedCrate { row, column: i })?;
+            let label = chars[i + 1..close].iter().collect::<String>();
+
+            let stack = numbers
+                .iter()
+                .position(|&(start, end, _)| start <= close && i <= end)
+                .ok_or(ParseStateError::Unaligned { row, column: i })?;
+            if stacks[stack].len() != layer {
+                return Err(ParseStateError::Floating {
+                    row,
+                    stack: stack + 1,
+                });
+            }
+            stacks[stack].push(label);
+
+            i = close + 1;
+        }
     }
 
-    initial_state
+    Ok(Stacks { stacks })
+}
+
+/**
+a move that doesn't read "move N from A to B"
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseInstructionError {
+    // counted from one, within the instructions or within the file they were read from
+    pub line: usize,
+    pub text: String,
+}
+
+impl fmt::Display for ParseInstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: expected 'move N from A to B', found '{}'",
+            self.line, self.text
+        )
+    }
 }
 
-pub fn parse_instructions(instructions: Vec<String>) -> Vec<[usize; 3]> {
-    let mut moves: Vec<[usize; 3]> = Vec::new();
+impl Error for ParseInstructionError {}
 
-    for instruction in instructions {
-        let temp: Vec<usize> = instruction
-            .split_whitespace()
-            .map(|s| s.parse().unwrap_or_default())
-            .collect::<Vec<usize>>();
-        moves.push([temp[1], temp[3], temp[5]]);
+pub fn parse_instruction(instruction: &str) -> Option<[usize; 3]> {
+    let words = instruction.split_whitespace().collect::<Vec<&str>>();
+    match words[..] {
+        ["move", number, "from", from, "to", to] => {
+            Some([number.parse().ok()?, from.parse().ok()?, to.parse().ok()?])
+        }
+        _ => None,
     }
-    moves
 }
 
-pub fn parse_input(lines: Lines<BufReader<File>>) -> (Vec<String>, Vec<[usize; 3]>) {
+pub fn parse_instructions(
+    instructions: Vec<String>,
+) -> Result<Instructions, ParseInstructionError> {
+    instructions
+        .into_iter()
+        .enumerate()
+        .map(|(i, text)| match parse_instruction(&text) {
+            Some(instruction) => Ok(instruction),
+            None => Err(ParseInstructionError { line: i + 1, text }),
+        })
+        .collect()
+}
+
+pub fn parse_input(
+    lines: Lines<BufReader<File>>,
+) -> Result<(Stacks, Instructions), Box<dyn Error>> {
+    let (initial_state, instructions, _) = parse_input_numbered(lines)?;
+    Ok((initial_state, instructions))
+}
+
+/**
+parse a puzzle file, also returning the file line each instruction is on
+*/
+pub fn parse_input_numbered(
+    lines: Lines<BufReader<File>>,
+) -> Result<(Stacks, Instructions, Vec<usize>), Box<dyn Error>> {
     let mut state_chunk: Vec<String> = Vec::new();
     let mut instructions_chunk: Vec<String> = Vec::new();
+    let mut numbers: Vec<usize> = Vec::new();
+    let mut in_drawing = true;
 
-    // split file into chunks and ditch any irrelevant lines
-    for line in lines {
-        let line = line.unwrap();
+    // the drawing runs up to the first blank line, then come the moves
+    for (i, line) in lines.enumerate() {
+        let line = line?;
 
-        if line.contains('[') {
+        if in_drawing && line.trim().is_empty() {
+            in_drawing = false;
+        } else if in_drawing {
             state_chunk.push(line);
-        } else if line.starts_with("move") {
+        } else if !line.trim().is_empty() {
             instructions_chunk.push(line);
-        } else {
-            continue;
+            numbers.push(i + 1);
         }
     }
 
     // parse chunks individually
-    let initial_state = parse_state(state_chunk);
-    let instructions = parse_instructions(instructions_chunk);
+    let initial_state = parse_state(state_chunk)?;
+    let instructions =
+        parse_instructions(instructions_chunk).map_err(|error| ParseInstructionError {
+            line: numbers[error.line - 1],
+            ..error
+        })?;
+
+    Ok((initial_state, instructions, numbers))
+}
 
-    (initial_state, instructions)
+/**
+how a crane breaks up a move: lifts of up to `size` crates from the top down,
+each either turned over or kept in order as it is set down
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lifts {
+    pub size: usize,
+    pub reversed: bool,
 }
 
-pub fn move_crates(initial_state: Vec<String>, instructions: Vec<[usize; 3]>) -> Vec<String> {
-    let mut state = initial_state
-        .iter()
-        .map(|s| s.chars().collect())
-        .collect::<Vec<Vec<char>>>();
+/**
+a crane model, deciding the order crates land in when several are moved at once
+*/
+pub trait Crane {
+    /**
+    how a move of `number` crates is broken into lifts, which is all a crane
+    decides and so all any engine needs to carry the move out
+    */
+    fn lifts(&self, number: usize) -> Lifts;
+
+    /**
+    move the top `number` crates of stack `from` onto stack `to`, both counted from one
+    */
+    fn lift<T>(&self, state: &mut [Vec<T>], [number, from, to]: [usize; 3]) {
+        // lifting onto the stack it came from puts every crate back as it was
+        if from == to {
+            return;
+        }
+        let Lifts { size, reversed } = self.lifts(number);
+        let mut remaining = number;
+        while remaining > 0 {
+            let lift = remaining.min(size.max(1));
+            let mut crates = take_top(&mut state[from - 1], lift);
+            if reversed {
+                crates.reverse();
+            }
+            state[to - 1].append(&mut crates);
+            remaining -= lift;
+        }
+    }
+
+    /**
+    lift after checking both stacks exist and there are enough crates to move
+    */
+    fn try_lift<T>(&self, state: &mut [Vec<T>], instruction: [usize; 3]) -> Result<(), MoveError> {
+        check_move_by(state.len(), |stack| state[stack].len(), instruction)?;
+        self.lift(state, instruction);
+        Ok(())
+    }
+}
 
-    for [number, from, to] in instructions {
-        for _ in 0..number {
-            let el = state[from - 1].pop().unwrap();
-            state[to - 1].push(el);
+/**
+lifts one crate at a time, so a move reverses the crates it carries
+*/
+pub struct CrateMover9000;
+
+/**
+lifts every crate in a move at once, keeping their order
+*/
+pub struct CrateMover9001;
+
+/**
+lifts up to `capacity` crates at once, larger moves are split into several
+lifts from the top down
+*/
+pub struct CappedCrane {
+    capacity: usize,
+}
+
+impl CappedCrane {
+    /**
+    a crane has to be able to lift at least one crate, or no move would finish
+    */
+    pub fn new(capacity: usize) -> Option<Self> {
+        match capacity {
+            0 => None,
+            capacity => Some(CappedCrane { capacity }),
         }
     }
 
-    state
-        .iter()
-        .map(|v| v.iter().map(|&c| c.to_string()).collect::<String>())
-        .collect::<Vec<String>>()
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
 }
 
-pub fn get_top_crates(final_state: Vec<String>) -> String {
-    final_state
-        .iter()
-        .map(|s| s.chars().nth_back(0).unwrap().to_string())
-        .collect::<String>()
+fn take_top<T>(stack: &mut Vec<T>, number: usize) -> Vec<T> {
+    let length = stack.len();
+    stack.split_off(length - number)
 }
 
-pub fn get_part1_top_crates(filename: &str) -> Result<String, Box<dyn Error>> {
-    // read in file
-    let lines = read_file(filename)?;
-    // parse input to get initial state and instructions
-    let (initial_state, instructions) = parse_input(lines);
-    // move crates
-    let final_state = move_crates(initial_state, instructions);
-    // take top crate of each stack
-    Ok(get_top_crates(final_state))
+impl Crane for CrateMover9000 {
+    fn lifts(&self, number: usize) -> Lifts {
+        // a crate at a time is the same as turning the whole move over at once
+        Lifts {
+            size: number,
+            reversed: true,
+        }
+    }
 }
 
-pub fn move_crates_9001(initial_state: Vec<String>, instructions: Vec<[usize; 3]>) -> Vec<String> {
-    let mut state = initial_state
-        .iter()
-        .map(|s| s.chars().collect())
-        .collect::<Vec<Vec<char>>>();
+impl Crane for CrateMover9001 {
+    fn lifts(&self, number: usize) -> Lifts {
+        Lifts {
+            size: number,
+            reversed: false,
+        }
+    }
+}
 
-    for [number, from, to] in instructions {
-        let length = state[from - 1].len();
-        let mut crates = state[from - 1].split_off(length - number);
-        state[to - 1].append(&mut crates)
+impl Crane for CappedCrane {
+    fn lifts(&self, number: usize) -> Lifts {
+        if self.capacity == 1 {
+            return CrateMover9000.lifts(number);
+        }
+        Lifts {
+            size: self.capacity,
+            reversed: false,
+        }
     }
+}
 
-    state
-        .iter()
-        .map(|v| v.iter().map(|&c| c.to_string()).collect::<String>())
-        .collect::<Vec<String>>()
+pub fn move_crates_with<C: Crane>(
+    crane: &C,
+    initial_state: Stacks,
+    instructions: Vec<[usize; 3]>,
+) -> Result<Stacks, InstructionError> {
+    let mut state = initial_state;
+
+    for (i, instruction) in instructions.into_iter().enumerate() {
+        // a failed lift leaves the stacks untouched, so their heights are still current
+        crane
+            .try_lift(&mut state.stacks, instruction)
+            .map_err(|error| InstructionError {
+                line: i + 1,
+                instruction,
+                error,
+                heights: state.stacks.iter().map(|s| s.len()).collect(),
+            })?;
+    }
+
+    Ok(state)
 }
 
-pub fn get_part2_top_crates(filename: &str) -> Result<String, Box<dyn Error>> {
+pub fn move_crates(
+    initial_state: Stacks,
+    instructions: Vec<[usize; 3]>,
+) -> Result<Stacks, InstructionError> {
+    move_crates_with(&CrateMover9000, initial_state, instructions)
+}
+
+pub fn move_crates_9001(
+    initial_state: Stacks,
+    instructions: Vec<[usize; 3]>,
+) -> Result<Stacks, InstructionError> {
+    move_crates_with(&CrateMover9001, initial_state, instructions)
+}
+
+pub fn get_top_crates(final_state: &Stacks) -> String {
+    final_state.top_crates()
+}
+
+pub fn get_top_crates_with<C: Crane>(filename: &str, crane: &C) -> Result<String, Box<dyn Error>> {
     // read in file
     let lines = read_file(filename)?;
     // parse input to get initial state and instructions
-    let (initial_state, instructions) = parse_input(lines);
+    let (initial_state, instructions) = parse_input(lines)?;
     // move crates
-    let final_state = move_crates_9001(initial_state, instructions);
+    let final_state = move_crates_with(crane, initial_state, instructions)?;
     // take top crate of each stack
-    Ok(get_top_crates(final_state))
+    Ok(get_top_crates(&final_state))
+}
+
+pub fn get_part1_top_crates(filename: &str) -> Result<String, Box<dyn Error>> {
+    get_top_crates_with(filename, &CrateMover9000)
+}
+
+pub fn get_part2_top_crates(filename: &str) -> Result<String, Box<dyn Error>> {
+    get_top_crates_with(filename, &CrateMover9001)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn to_strings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|&s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_capped_crane() {
+        let initial_state = Stacks::from_labels(&["A", "BCDE", ""]);
+        let instructions = vec![[3, 2, 3]];
+
+        // lifts "DE" first, then "C"
+        let final_state = Stacks::from_labels(&["A", "B", "DEC"]);
+
+        assert_eq!(
+            move_crates_with(&CappedCrane::new(2).unwrap(), initial_state, instructions).unwrap(),
+            final_state
+        );
+    }
+
+    #[test]
+    fn test_capped_crane_zero_capacity() {
+        assert!(CappedCrane::new(0).is_none());
+        assert_eq!(CappedCrane::new(3).unwrap().capacity(), 3);
+    }
+
+    #[test]
+    fn test_capped_crane_matches_models() {
+        // a capacity of one is the 9000, an unlimited capacity is the 9001
+        assert_eq!(
+            get_top_crates_with("input/day5.test", &CappedCrane::new(1).unwrap()).unwrap(),
+            "CMZ"
+        );
+        assert_eq!(
+            get_top_crates_with("input/day5.test", &CappedCrane::new(usize::MAX).unwrap()).unwrap(),
+            "MCD"
+        );
+    }
+
+    #[test]
+    fn test_crate_mover_9000_same_stack() {
+        let mut state = vec![vec!['A', 'B', 'C']];
+        CrateMover9000.lift(&mut state, [2, 1, 1]);
+        assert_eq!(state, vec![vec!['A', 'B', 'C']]);
+    }
+
     #[test]
     fn test_get_part2_top_crates() {
         assert_eq!(get_part2_top_crates("input/day5.test").unwrap(), "MCD")
@@ -143,18 +579,15 @@ mod tests {
 
     #[test]
     fn test_move_crates_9001() {
-        let initial_state = vec!["A", "BC", "D"]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
+        let initial_state = Stacks::from_labels(&["A", "BC", "D"]);
         let instructions = vec![[2, 2, 1]];
 
-        let final_state = vec!["ABC", "", "D"]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
+        let final_state = Stacks::from_labels(&["ABC", "", "D"]);
 
-        assert_eq!(move_crates_9001(initial_state, instructions), final_state);
+        assert_eq!(
+            move_crates_9001(initial_state, instructions).unwrap(),
+            final_state
+        );
     }
 
     #[test]
@@ -164,72 +597,208 @@ mod tests {
 
     #[test]
     fn test_get_top_crates() {
-        let input = vec!["C", "M", "PDNZ"];
-        let input_cast = input
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
+        let input = Stacks::from_labels(&["C", "M", "PDNZ"]);
 
-        assert_eq!(get_top_crates(input_cast), "CMZ");
+        assert_eq!(get_top_crates(&input), "CMZ");
     }
 
     #[test]
     fn test_move_crates() {
-        let initial_state = vec!["A", "BC", "D"]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
+        let initial_state = Stacks::from_labels(&["A", "BC", "D"]);
         let instructions = vec![[2, 2, 1]];
 
-        let final_state = vec!["ACB", "", "D"]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
+        let final_state = Stacks::from_labels(&["ACB", "", "D"]);
+
+        assert_eq!(
+            move_crates(initial_state, instructions).unwrap(),
+            final_state
+        );
+    }
 
-        assert_eq!(move_crates(initial_state, instructions), final_state);
+    #[test]
+    fn test_move_crates_errors() {
+        let initial_state = Stacks::from_labels(&["A", "BCD", ""]);
+        let err = move_crates(initial_state.clone(), vec![[1, 2, 1], [5, 2, 3]]).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.heights, vec![2, 2, 0]);
+        assert!(move_crates_9001(initial_state.clone(), vec![[4, 2, 1]]).is_err());
+        assert!(move_crates_9001(initial_state, vec![[1, 0, 1]]).is_err());
+        assert!(get_part1_top_crates("input/day5_invalid.test").is_err());
     }
 
     #[test]
     fn test_parse_input() {
         let lines = read_file("input/day5.test").unwrap();
-        let output_initial_state = vec!["ZN", "MCD", "P"]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
+        let output_initial_state = Stacks::from_labels(&["ZN", "MCD", "P"]);
         let output_instructions = vec![[1, 2, 1], [3, 1, 3], [2, 2, 1], [1, 1, 2]];
         assert_eq!(
-            parse_input(lines),
+            parse_input(lines).unwrap(),
             (output_initial_state, output_instructions)
         );
     }
 
     #[test]
     fn test_parse_instructions() {
-        let lines = vec![
+        let lines = to_strings(&[
             "move 1 from 2 to 1",
             "move 3 from 1 to 3",
             "move 2 from 2 to 1",
             "move 1 from 1 to 2",
-        ]
-        .iter()
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
+        ]);
 
         let output_moves = vec![[1, 2, 1], [3, 1, 3], [2, 2, 1], [1, 1, 2]];
-        assert_eq!(parse_instructions(lines), output_moves);
+        assert_eq!(parse_instructions(lines), Ok(output_moves));
+    }
+
+    #[test]
+    fn test_parse_instructions_malformed() {
+        let lines = to_strings(&["move 1 from 2 to 1", "move 1 from 2"]);
+        assert_eq!(
+            parse_instructions(lines),
+            Err(ParseInstructionError {
+                line: 2,
+                text: String::from("move 1 from 2")
+            })
+        );
+        assert_eq!(parse_instruction("move x from 1 to 2"), None);
+        assert_eq!(parse_instruction("move 1 from 1 to -2"), None);
+        assert_eq!(parse_instruction("move 1 from 1 to 2 please"), None);
+    }
+
+    #[test]
+    fn test_parse_input_malformed() {
+        let err = parse_input(read_file("input/day5_malformed.test").unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 7: expected 'move N from A to B', found 'move 3 from 1'"
+        );
     }
 
     #[test]
     fn test_parse_state() {
-        let lines = vec!["    [D]     ", "[N] [C]     ", "[Z] [M] [P] "]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
-        let output_initial_state = vec!["ZN", "MCD", "P"]
-            .iter()
-            .map(|&s| s.to_string())
-            .collect::<Vec<String>>();
-        assert_eq!(parse_state(lines), output_initial_state);
+        let lines = to_strings(&[
+            "    [D]     ",
+            "[N] [C]     ",
+            "[Z] [M] [P] ",
+            " 1   2   3 ",
+        ]);
+        let output_initial_state = Stacks::from_labels(&["ZN", "MCD", "P"]);
+        assert_eq!(parse_state(lines).unwrap(), output_initial_state);
+    }
+
+    #[test]
+    fn test_parse_state_trimmed() {
+        let lines = to_strings(&["    [D]", "[N] [C]", "[Z] [M] [P]", " 1   2   3"]);
+        let output_initial_state = Stacks::from_labels(&["ZN", "MCD", "P"]);
+        assert_eq!(parse_state(lines).unwrap(), output_initial_state);
+    }
+
+    #[test]
+    fn test_parse_state_many_stacks() {
+        let lines = to_strings(&[
+            "                                            [L]",
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [K] [M]",
+            " 1   2   3   4   5   6   7   8   9  10  11  12 ",
+        ]);
+        let state = parse_state(lines).unwrap();
+        assert_eq!(state.len(), 12);
+        assert_eq!(state.stacks[11], vec!["M", "L"]);
+        assert_eq!(state.top_crates(), "ABCDEFGHIJKL");
+    }
+
+    #[test]
+    fn test_parse_state_wide_labels() {
+        let lines = to_strings(&["     [CD]", "[AB] [EF] [G]", "  1    2   3"]);
+        let state = parse_state(lines).unwrap();
+        assert_eq!(state.stacks, vec![vec!["AB"], vec!["EF", "CD"], vec!["G"]]);
+    }
+
+    #[test]
+    fn test_parse_state_errors() {
+        assert_eq!(parse_state(vec![]), Err(ParseStateError::MissingNumbering));
+        assert_eq!(
+            parse_state(to_strings(&["[A] [B]", " 1   3"])),
+            Err(ParseStateError::BadNumbering {
+                found: "1   3".to_string()
+            })
+        );
+        assert_eq!(
+            parse_state(to_strings(&["[A]     [B]", " 1   2"])),
+            Err(ParseStateError::Unaligned { row: 0, column: 8 })
+        );
+        assert_eq!(
+            parse_state(to_strings(&["[A]", "    [B]", " 1   2"])),
+            Err(ParseStateError::Floating { row: 0, stack: 1 })
+        );
+        assert_eq!(
+            parse_state(to_strings(&["[A", " 1"])),
+            Err(ParseStateError::UnclosedCrate { row: 0, column: 0 })
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let drawing = read_file("input/day5.test")
+            .unwrap()
+            .take(4)
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        let state = Stacks::from_labels(&["ZN", "MCD", "P"]);
+        assert_eq!(state.render(), drawing);
+    }
+
+    #[test]
+    fn test_render_wide() {
+        let state = Stacks {
+            stacks: vec![vec!["AB".to_string()], vec![], vec!["C".to_string()]],
+        };
+        assert_eq!(state.to_string(), "[AB]      [C] \n  1    2    3 ");
+    }
+
+    /**
+    pseudo-random stacks from a simple LCG so the round trip covers odd shapes
+    */
+    fn generated_stacks(seed: u64, count: usize, wide: bool) -> Stacks {
+        let mut x = seed;
+        let mut next = move |m: u64| {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (x >> 33) % m
+        };
+        let stacks = (0..count)
+            .map(|_| {
+                (0..next(6))
+                    .map(|_| {
+                        let length = if wide { 1 + next(3) } else { 1 };
+                        (0..length)
+                            .map(|_| (b'A' + next(26) as u8) as char)
+                            .collect::<String>()
+                    })
+                    .collect()
+            })
+            .collect();
+        Stacks { stacks }
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        for seed in 0..50 {
+            for (count, wide) in [(3, false), (9, false), (12, false), (4, true), (15, true)] {
+                let state = generated_stacks(seed, count, wide);
+                assert_eq!(parse_state(state.render()).unwrap(), state);
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_state() {
+        let filename = std::env::temp_dir().join("aoc2022_day5_state.test");
+        let filename = filename.to_str().unwrap();
+        let state = Stacks::from_labels(&["ZN", "MCD", "P"]);
+        write_state(filename, &state).unwrap();
+        assert_eq!(read_state(filename).unwrap(), state);
+        assert_eq!(read_state("input/day5.test").unwrap(), state);
     }
 
     #[test]
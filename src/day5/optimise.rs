@@ -0,0 +1,228 @@
+use std::error::Error;
+
+use super::super::read_file;
+use super::validate::InstructionError;
+use super::{move_crates_with, parse_input, Crane, Stacks};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optimised {
+    pub program: Vec<[usize; 3]>,
+    // instruction counts before and after
+    pub before: usize,
+    pub after: usize,
+}
+
+/**
+check two programs leave the same stacks when run from the same state
+*/
+pub fn equivalent<C: Crane>(
+    crane: &C,
+    initial_state: &Stacks,
+    a: &[[usize; 3]],
+    b: &[[usize; 3]],
+) -> Result<bool, InstructionError> {
+    let end_a = move_crates_with(crane, initial_state.clone(), a.to_vec())?;
+    let end_b = move_crates_with(crane, initial_state.clone(), b.to_vec())?;
+    Ok(end_a == end_b)
+}
+
+fn touches([_, from, to]: [usize; 3], stack: usize) -> bool {
+    from == stack || to == stack
+}
+
+/**
+shorter replacements to try for a pair of moves that could run back to back
+*/
+fn candidates([n1, a1, b1]: [usize; 3], [n2, a2, b2]: [usize; 3]) -> Vec<Vec<[usize; 3]>> {
+    let mut candidates = vec![vec![]];
+    // more from the same stack to the same stack
+    if a1 == a2 && b1 == b2 {
+        candidates.push(vec![[n1 + n2, a1, b1]]);
+    }
+    // the same crates carried straight on
+    if n1 == n2 && b1 == a2 {
+        candidates.push(vec![[n1, a1, b2]]);
+    }
+    candidates
+}
+
+/**
+rewrite a program into a shorter one leaving the same stacks under the given
+crane, e.g. merging repeated moves between the same stacks or dropping moves
+that cancel out
+
+a move is paired with the next one touching the same stacks as long as the
+moves between them touch neither, since those can run in any order; each
+rewrite is checked by simulating the pair from the state it runs on, and the
+whole result is checked against the original before it is returned
+*/
+pub fn optimise<C: Crane>(
+    crane: &C,
+    initial_state: &Stacks,
+    program: &[[usize; 3]],
+) -> Result<Optimised, InstructionError> {
+    // fail early on programs that can't run at all
+    let end = move_crates_with(crane, initial_state.clone(), program.to_vec())?;
+
+    let mut current = program.to_vec();
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+        let mut state = initial_state.clone();
+        let mut i = 0;
+
+        while i < current.len() {
+            let first = current[i];
+            let mut after = state.clone();
+            crane.lift(&mut after.stacks, first);
+
+            // moves that change nothing
+            if after == state {
+                current.remove(i);
+                changed = true;
+                continue;
+            }
+
+            let [_, from, to] = first;
+            let partner = (i + 1..current.len())
+                .find(|&j| touches(current[j], from) || touches(current[j], to));
+
+            if let Some(j) = partner {
+                let second = current[j];
+                let [_, from2, to2] = second;
+                let independent = current[i + 1..j]
+                    .iter()
+                    .all(|&m| !touches(m, from2) && !touches(m, to2));
+
+                if independent {
+                    let mut both = after.clone();
+                    crane.lift(&mut both.stacks, second);
+
+                    let replacement = candidates(first, second).into_iter().find(|c| {
+                        move_crates_with(crane, state.clone(), c.clone()).is_ok_and(|s| s == both)
+                    });
+                    if let Some(replacement) = replacement {
+                        current.remove(j);
+                        current.splice(i..i + 1, replacement);
+                        changed = true;
+                        continue;
+                    }
+                }
+            }
+
+            state = after;
+            i += 1;
+        }
+    }
+
+    // every rewrite was checked locally, but make sure the whole still agrees
+    if move_crates_with(crane, initial_state.clone(), current.clone())? != end {
+        current = program.to_vec();
+    }
+
+    Ok(Optimised {
+        before: program.len(),
+        after: current.len(),
+        program: current,
+    })
+}
+
+pub fn optimise_file<C: Crane>(crane: &C, filename: &str) -> Result<Optimised, Box<dyn Error>> {
+    let (initial_state, instructions) = parse_input(read_file(filename)?)?;
+    Ok(optimise(crane, &initial_state, &instructions)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CappedCrane, CrateMover9000, CrateMover9001};
+    use super::*;
+
+    fn state() -> Stacks {
+        Stacks::from_labels(&["ABC", "DE", "F"])
+    }
+
+    #[test]
+    fn test_optimise_cancelling_moves() {
+        let program = [[2, 1, 2], [2, 2, 1], [1, 3, 1]];
+        let optimised = optimise(&CrateMover9000, &state(), &program).unwrap();
+        assert_eq!(optimised.program, vec![[1, 3, 1]]);
+        assert_eq!((optimised.before, optimised.after), (3, 1));
+    }
+
+    #[test]
+    fn test_optimise_merges_9000_only() {
+        let program = [[1, 1, 2], [2, 1, 2]];
+        let optimised = optimise(&CrateMover9000, &state(), &program).unwrap();
+        assert_eq!(optimised.program, vec![[3, 1, 2]]);
+
+        // the 9001 would land them in a different order
+        let optimised = optimise(&CrateMover9001, &state(), &program).unwrap();
+        assert_eq!(optimised.program, program.to_vec());
+    }
+
+    #[test]
+    fn test_optimise_chains_9001_only() {
+        let program = [[2, 1, 2], [2, 2, 3]];
+        let optimised = optimise(&CrateMover9001, &state(), &program).unwrap();
+        assert_eq!(optimised.program, vec![[2, 1, 3]]);
+
+        let optimised = optimise(&CrateMover9000, &state(), &program).unwrap();
+        assert_eq!(optimised.after, 2);
+    }
+
+    #[test]
+    fn test_optimise_across_independent_moves() {
+        // the move between touches neither stack 1 nor stack 2
+        let state = Stacks::from_labels(&["ABC", "DE", "F", "G"]);
+        let program = [[1, 1, 2], [1, 3, 4], [1, 2, 1]];
+        let optimised = optimise(&CrateMover9001, &state, &program).unwrap();
+        assert_eq!(optimised.program, vec![[1, 3, 4]]);
+    }
+
+    #[test]
+    fn test_optimise_drops_no_ops() {
+        let program = [[0, 1, 2], [2, 1, 1], [1, 2, 3]];
+        let optimised = optimise(&CappedCrane::new(2).unwrap(), &state(), &program).unwrap();
+        assert_eq!(optimised.program, vec![[1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_optimise_file_is_equivalent() {
+        let (initial_state, instructions) =
+            parse_input(read_file("input/day5.test").unwrap()).unwrap();
+        let optimised = optimise_file(&CrateMover9000, "input/day5.test").unwrap();
+        assert_eq!(optimised.before, 4);
+        assert!(optimised.after <= 4);
+        assert!(equivalent(
+            &CrateMover9000,
+            &initial_state,
+            &instructions,
+            &optimised.program
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_optimise_invalid_program() {
+        assert!(optimise(&CrateMover9000, &state(), &[[5, 1, 2]]).is_err());
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert!(equivalent(
+            &CrateMover9000,
+            &state(),
+            &[[1, 1, 2], [1, 1, 2]],
+            &[[2, 1, 2]]
+        )
+        .unwrap());
+        assert!(!equivalent(
+            &CrateMover9001,
+            &state(),
+            &[[1, 1, 2], [1, 1, 2]],
+            &[[2, 1, 2]]
+        )
+        .unwrap());
+    }
+}
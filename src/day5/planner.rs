@@ -0,0 +1,255 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use super::{read_state, Crane, Stacks};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    // the two states don't hold the same crates on the same number of stacks
+    Unreachable,
+    // the search gave up after expanding this many states
+    BudgetExhausted { expanded: usize },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::Unreachable => write!(f, "target state has different crates or stacks"),
+            PlanError::BudgetExhausted { expanded } => {
+                write!(f, "no plan found after expanding {} states", expanded)
+            }
+        }
+    }
+}
+
+impl Error for PlanError {}
+
+/**
+fewest moves still needed, never an overestimate
+
+above the part of each stack already matching the target, every crate has to
+be moved off, and anything the target has past that part has to be moved on;
+a move has one source and one target, so count stacks of each kind
+*/
+fn heuristic(state: &Stacks, target: &Stacks) -> usize {
+    let mut sources = 0;
+    let mut targets = 0;
+
+    for (current, wanted) in state.stacks.iter().zip(&target.stacks) {
+        let matching = current
+            .iter()
+            .zip(wanted)
+            .take_while(|(a, b)| a == b)
+            .count();
+        if current.len() > matching {
+            sources += 1;
+        }
+        if wanted.len() > matching {
+            targets += 1;
+        }
+    }
+
+    sources.max(targets)
+}
+
+fn same_crates(a: &Stacks, b: &Stacks) -> bool {
+    let mut a_labels = a.stacks.iter().flatten().collect::<Vec<&String>>();
+    let mut b_labels = b.stacks.iter().flatten().collect::<Vec<&String>>();
+    a_labels.sort();
+    b_labels.sort();
+    a.len() == b.len() && a_labels == b_labels
+}
+
+/**
+A* search for the shortest program taking one state to another, expanding at
+most `budget` states
+*/
+pub fn plan<C: Crane>(
+    crane: &C,
+    initial_state: &Stacks,
+    target: &Stacks,
+    budget: usize,
+) -> Result<Vec<[usize; 3]>, PlanError> {
+    if !same_crates(initial_state, target) {
+        return Err(PlanError::Unreachable);
+    }
+
+    // every state seen, with how it was first reached at its best cost
+    let mut states: Vec<Stacks> = vec![initial_state.clone()];
+    let mut parents: Vec<Option<(usize, [usize; 3])>> = vec![None];
+    let mut costs: HashMap<Stacks, usize> = HashMap::from([(initial_state.clone(), 0)]);
+
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((heuristic(initial_state, target), 0, 0)));
+    let mut expanded = 0;
+
+    while let Some(Reverse((_, cost, id))) = open.pop() {
+        // skip entries left behind after a cheaper route was found
+        if costs[&states[id]] < cost {
+            continue;
+        }
+
+        if states[id] == *target {
+            let mut program = Vec::new();
+            let mut at = id;
+            while let Some((parent, instruction)) = parents[at] {
+                program.push(instruction);
+                at = parent;
+            }
+            program.reverse();
+            return Ok(program);
+        }
+
+        if expanded == budget {
+            return Err(PlanError::BudgetExhausted { expanded });
+        }
+        expanded += 1;
+
+        let stack_ct = states[id].len();
+        for from in 1..=stack_ct {
+            for to in (1..=stack_ct).filter(|&to| to != from) {
+                for number in 1..=states[id].stacks[from - 1].len() {
+                    let instruction = [number, from, to];
+                    let mut next = states[id].clone();
+                    crane.lift(&mut next.stacks, instruction);
+
+                    if costs.get(&next).is_some_and(|&c| c <= cost + 1) {
+                        continue;
+                    }
+                    costs.insert(next.clone(), cost + 1);
+
+                    let estimate = cost + 1 + heuristic(&next, target);
+                    states.push(next);
+                    parents.push(Some((id, instruction)));
+                    open.push(Reverse((estimate, cost + 1, states.len() - 1)));
+                }
+            }
+        }
+    }
+
+    Err(PlanError::Unreachable)
+}
+
+/**
+write a program in the form `parse_instructions` reads
+*/
+pub fn format_program(program: &[[usize; 3]]) -> Vec<String> {
+    program
+        .iter()
+        .map(|[number, from, to]| format!("move {} from {} to {}", number, from, to))
+        .collect()
+}
+
+pub fn plan_between_files<C: Crane>(
+    crane: &C,
+    initial_file: &str,
+    target_file: &str,
+    budget: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let initial_state = read_state(initial_file)?;
+    let target = read_state(target_file)?;
+    Ok(format_program(&plan(
+        crane,
+        &initial_state,
+        &target,
+        budget,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::read_file;
+    use super::super::{
+        move_crates_with, parse_input, parse_instructions, CrateMover9000, CrateMover9001,
+    };
+    use super::*;
+
+    fn sample() -> (Stacks, Vec<[usize; 3]>) {
+        parse_input(read_file("input/day5.test").unwrap()).unwrap()
+    }
+
+    fn assert_plan_reaches_target<C: Crane>(crane: &C) {
+        let (initial_state, instructions) = sample();
+        let target = move_crates_with(crane, initial_state.clone(), instructions.clone()).unwrap();
+
+        let program = plan(crane, &initial_state, &target, 100_000).unwrap();
+
+        // never longer than the program that produced the target
+        assert!(program.len() <= instructions.len());
+        let replayed = parse_instructions(format_program(&program)).unwrap();
+        assert_eq!(
+            move_crates_with(crane, initial_state, replayed).unwrap(),
+            target
+        );
+    }
+
+    #[test]
+    fn test_plan_reaches_target() {
+        assert_plan_reaches_target(&CrateMover9000);
+        assert_plan_reaches_target(&CrateMover9001);
+    }
+
+    #[test]
+    fn test_plan_is_shortest() {
+        let initial_state = Stacks::from_labels(&["ABC", "", ""]);
+        // the 9001 moves all three at once, the 9000 has to flip them twice
+        let target = Stacks::from_labels(&["", "", "ABC"]);
+        assert_eq!(
+            plan(&CrateMover9001, &initial_state, &target, 1000).unwrap(),
+            vec![[3, 1, 3]]
+        );
+        assert_eq!(
+            plan(&CrateMover9000, &initial_state, &target, 1000)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_plan_already_there() {
+        let (initial_state, _) = sample();
+        assert_eq!(
+            plan(&CrateMover9000, &initial_state, &initial_state, 0),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn test_plan_unreachable() {
+        let initial_state = Stacks::from_labels(&["AB", ""]);
+        let target = Stacks::from_labels(&["A", "C"]);
+        assert_eq!(
+            plan(&CrateMover9000, &initial_state, &target, 1000),
+            Err(PlanError::Unreachable)
+        );
+    }
+
+    #[test]
+    fn test_plan_budget() {
+        let initial_state = Stacks::from_labels(&["ABCDEF", "", ""]);
+        let target = Stacks::from_labels(&["", "FDB", "ECA"]);
+        assert_eq!(
+            plan(&CrateMover9000, &initial_state, &target, 1),
+            Err(PlanError::BudgetExhausted { expanded: 1 })
+        );
+    }
+
+    #[test]
+    fn test_heuristic() {
+        let state = Stacks::from_labels(&["ABC", "D", ""]);
+        let target = Stacks::from_labels(&["A", "D", "CB"]);
+        assert_eq!(heuristic(&state, &target), 1);
+        assert_eq!(heuristic(&target, &target), 0);
+    }
+
+    #[test]
+    fn test_format_program() {
+        assert_eq!(
+            format_program(&[[1, 2, 1], [3, 1, 3]]),
+            vec!["move 1 from 2 to 1", "move 3 from 1 to 3"]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::super::read_file;
+use super::validate::{first_error, InstructionError};
+use super::{parse_input, Crane, Stacks};
+
+/**
+a crate is known by where it started: its stack, counted from one, and its
+height on that stack, counted from zero at the bottom
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CrateId {
+    pub stack: usize,
+    pub height: usize,
+}
+
+/**
+where a crate sat after a step, step 0 being the initial state
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub step: usize,
+    pub stack: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    labels: HashMap<CrateId, String>,
+    paths: HashMap<CrateId, Vec<Position>>,
+    // tops[step][stack - 1] is the crate on top of a stack after that step
+    tops: Vec<Vec<Option<CrateId>>>,
+}
+
+/**
+run a program keeping track of every crate it moves
+*/
+pub fn track<C: Crane>(
+    crane: &C,
+    initial_state: &Stacks,
+    instructions: &[[usize; 3]],
+) -> Result<Provenance, InstructionError> {
+    if let Some(error) = first_error(initial_state, instructions) {
+        return Err(error);
+    }
+
+    let mut labels = HashMap::new();
+    let mut paths = HashMap::new();
+    let mut state: Vec<Vec<CrateId>> = Vec::new();
+
+    for (s, stack) in initial_state.stacks.iter().enumerate() {
+        let mut ids = Vec::new();
+        for (height, label) in stack.iter().enumerate() {
+            let id = CrateId {
+                stack: s + 1,
+                height,
+            };
+            labels.insert(id, label.clone());
+            paths.insert(
+                id,
+                vec![Position {
+                    step: 0,
+                    stack: s + 1,
+                    height,
+                }],
+            );
+            ids.push(id);
+        }
+        state.push(ids);
+    }
+
+    let tops_of = |state: &Vec<Vec<CrateId>>| state.iter().map(|s| s.last().copied()).collect();
+    let mut tops = vec![tops_of(&state)];
+
+    for (i, &instruction) in instructions.iter().enumerate() {
+        let [number, _, to] = instruction;
+        crane.lift(&mut state, instruction);
+
+        // the moved crates are now the top `number` of the target stack
+        let target = &state[to - 1];
+        let base = target.len() - number;
+        for (height, crate_id) in target.iter().enumerate().skip(base) {
+            paths.get_mut(crate_id).unwrap().push(Position {
+                step: i + 1,
+                stack: to,
+                height,
+            });
+        }
+        tops.push(tops_of(&state));
+    }
+
+    Ok(Provenance {
+        labels,
+        paths,
+        tops,
+    })
+}
+
+impl Provenance {
+    pub fn steps(&self) -> usize {
+        self.tops.len() - 1
+    }
+
+    pub fn label(&self, id: CrateId) -> Option<&str> {
+        self.labels.get(&id).map(|l| l.as_str())
+    }
+
+    /**
+    every crate with the given label, as labels needn't be unique
+    */
+    pub fn find(&self, label: &str) -> Vec<CrateId> {
+        let mut ids = self
+            .labels
+            .iter()
+            .filter(|(_, l)| l.as_str() == label)
+            .map(|(&id, _)| id)
+            .collect::<Vec<CrateId>>();
+        ids.sort();
+        ids
+    }
+
+    /**
+    where the crate started, then where every move that carried it left it
+    */
+    pub fn path(&self, id: CrateId) -> Option<&[Position]> {
+        self.paths.get(&id).map(|p| p.as_slice())
+    }
+
+    /**
+    the step that last moved a crate, or None if it never moved
+    */
+    pub fn last_moved(&self, id: CrateId) -> Option<usize> {
+        self.path(id)?
+            .last()
+            .map(|p| p.step)
+            .filter(|&step| step > 0)
+    }
+
+    /**
+    the crate on top of a stack, counted from one, after a step
+    */
+    pub fn top_after(&self, stack: usize, step: usize) -> Option<CrateId> {
+        *self.tops.get(step)?.get(stack.checked_sub(1)?)?
+    }
+
+    /**
+    each final top crate and the step that put it there, if any
+    */
+    pub fn explain_top_crates(&self) -> Vec<(usize, CrateId, Option<usize>)> {
+        self.tops[self.steps()]
+            .iter()
+            .enumerate()
+            .filter_map(|(s, top)| top.map(|id| (s + 1, id, self.last_moved(id))))
+            .collect()
+    }
+}
+
+pub fn track_file<C: Crane>(crane: &C, filename: &str) -> Result<Provenance, Box<dyn Error>> {
+    let (initial_state, instructions) = parse_input(read_file(filename)?)?;
+    Ok(track(crane, &initial_state, &instructions)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CrateMover9000, CrateMover9001};
+    use super::*;
+
+    fn id(stack: usize, height: usize) -> CrateId {
+        CrateId { stack, height }
+    }
+
+    #[test]
+    fn test_explain_top_crates() {
+        let provenance = track_file(&CrateMover9000, "input/day5.test").unwrap();
+        let labels = provenance
+            .explain_top_crates()
+            .iter()
+            .map(|&(_, id, _)| provenance.label(id).unwrap())
+            .collect::<String>();
+        assert_eq!(labels, "CMZ");
+        assert_eq!(
+            provenance.explain_top_crates(),
+            vec![
+                (1, id(2, 1), Some(3)),
+                (2, id(2, 0), Some(4)),
+                (3, id(1, 0), Some(2))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path() {
+        let provenance = track_file(&CrateMover9000, "input/day5.test").unwrap();
+        // D goes onto stack 1, then on to stack 3 where it stays
+        let d = provenance.find("D")[0];
+        let path = provenance.path(d).unwrap();
+        let stacks = path.iter().map(|p| (p.step, p.stack)).collect::<Vec<_>>();
+        assert_eq!(stacks, vec![(0, 2), (1, 1), (2, 3)]);
+        assert_eq!(provenance.last_moved(d), Some(2));
+    }
+
+    #[test]
+    fn test_top_after() {
+        let provenance = track_file(&CrateMover9001, "input/day5.test").unwrap();
+        assert_eq!(provenance.top_after(1, 0), Some(id(1, 1)));
+        assert_eq!(provenance.top_after(1, 2), None);
+        assert_eq!(provenance.top_after(4, 0), None);
+        assert_eq!(provenance.top_after(1, 99), None);
+    }
+
+    #[test]
+    fn test_find_repeated_labels() {
+        let state = Stacks::from_labels(&["AA", "B"]);
+        let provenance = track(&CrateMover9001, &state, &[[2, 1, 2]]).unwrap();
+        assert_eq!(provenance.find("A"), vec![id(1, 0), id(1, 1)]);
+        assert_eq!(provenance.top_after(2, 1), Some(id(1, 1)));
+    }
+
+    #[test]
+    fn test_track_invalid() {
+        let state = Stacks::from_labels(&["A"]);
+        assert!(track(&CrateMover9000, &state, &[[2, 1, 1]]).is_err());
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::super::read_file;
+use super::validate::{check_move_by, InstructionError, MoveError};
+use super::{parse_input, Crane, Lifts, Stacks};
+
+const NIL: usize = 0;
+
+#[derive(Debug, Clone)]
+struct Node {
+    label: usize,
+    priority: u64,
+    size: usize,
+    left: usize,
+    right: usize,
+    // the children, and everything below them, still need flipping
+    reversed: bool,
+}
+
+/**
+stacks held as implicit treaps, so a move of any number of crates is a split
+and a merge costing O(log n) rather than one push per crate, and turning a run
+over is a flag pushed down lazily
+
+any crane can drive it through the lifts it breaks a move into, each lift
+costing a split and a merge, so a crane taking c crates at a time moves n of
+them in O(n/c log n)
+*/
+#[derive(Debug, Clone)]
+pub struct RopeStacks {
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+    labels: Vec<String>,
+    seed: u64,
+}
+
+impl RopeStacks {
+    pub fn new(state: &Stacks) -> Self {
+        let mut rope = RopeStacks {
+            // node 0 stands in for an empty tree
+            nodes: vec![Node {
+                label: 0,
+                priority: 0,
+                size: 0,
+                left: NIL,
+                right: NIL,
+                reversed: false,
+            }],
+            roots: Vec::with_capacity(state.len()),
+            labels: Vec::new(),
+            seed: 0x9e3779b97f4a7c15,
+        };
+
+        // each label is stored once and crates refer to it by index
+        let mut interned = HashMap::new();
+        for stack in &state.stacks {
+            let mut root = NIL;
+            for label in stack {
+                let label = *interned.entry(label).or_insert_with(|| {
+                    rope.labels.push(label.clone());
+                    rope.labels.len() - 1
+                });
+                let node = rope.node(label);
+                root = rope.merge(root, node);
+            }
+            rope.roots.push(root);
+        }
+        rope
+    }
+
+    fn random(&mut self) -> u64 {
+        // xorshift64, a fixed seed keeps runs repeatable
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    fn node(&mut self, label: usize) -> usize {
+        let priority = self.random();
+        self.nodes.push(Node {
+            label,
+            priority,
+            size: 1,
+            left: NIL,
+            right: NIL,
+            reversed: false,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn size(&self, t: usize) -> usize {
+        self.nodes[t].size
+    }
+
+    fn update(&mut self, t: usize) {
+        let (left, right) = (self.nodes[t].left, self.nodes[t].right);
+        self.nodes[t].size = 1 + self.size(left) + self.size(right);
+    }
+
+    fn flip(&mut self, t: usize) {
+        if t != NIL {
+            self.nodes[t].reversed ^= true;
+        }
+    }
+
+    fn push_down(&mut self, t: usize) {
+        if self.nodes[t].reversed {
+            let node = &mut self.nodes[t];
+            node.reversed = false;
+            std::mem::swap(&mut node.left, &mut node.right);
+            let (left, right) = (node.left, node.right);
+            self.flip(left);
+            self.flip(right);
+        }
+    }
+
+    /**
+    split off the bottom k crates of a tree from the rest
+    */
+    fn split(&mut self, t: usize, k: usize) -> (usize, usize) {
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        self.push_down(t);
+        let left = self.nodes[t].left;
+        if self.size(left) >= k {
+            let (a, b) = self.split(left, k);
+            self.nodes[t].left = b;
+            self.update(t);
+            (a, t)
+        } else {
+            let right = self.nodes[t].right;
+            let (a, b) = self.split(right, k - self.size(left) - 1);
+            self.nodes[t].right = a;
+            self.update(t);
+            (t, b)
+        }
+    }
+
+    /**
+    stack tree b on top of tree a
+    */
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            self.push_down(a);
+            let right = self.nodes[a].right;
+            self.nodes[a].right = self.merge(right, b);
+            self.update(a);
+            a
+        } else {
+            self.push_down(b);
+            let left = self.nodes[b].left;
+            self.nodes[b].left = self.merge(a, left);
+            self.update(b);
+            b
+        }
+    }
+
+    pub fn heights(&self) -> Vec<usize> {
+        self.roots.iter().map(|&r| self.size(r)).collect()
+    }
+
+    pub fn lift<C: Crane>(&mut self, crane: &C, instruction: [usize; 3]) -> Result<(), MoveError> {
+        check_move_by(self.roots.len(), |s| self.size(self.roots[s]), instruction)?;
+
+        let [number, from, to] = instruction;
+        // every crane puts a move onto the stack it came from back as it was
+        if from == to {
+            return Ok(());
+        }
+
+        let Lifts { size, reversed } = crane.lifts(number);
+        let mut remaining = number;
+        while remaining > 0 {
+            let lift = remaining.min(size.max(1));
+            let source = self.roots[from - 1];
+            let keep = self.size(source) - lift;
+            let (rest, top) = self.split(source, keep);
+            if reversed {
+                self.flip(top);
+            }
+            self.roots[from - 1] = rest;
+            self.roots[to - 1] = self.merge(self.roots[to - 1], top);
+            remaining -= lift;
+        }
+        Ok(())
+    }
+
+    pub fn top_crates(&mut self) -> String {
+        let mut tops = String::new();
+        for s in 0..self.roots.len() {
+            let mut t = self.roots[s];
+            if t == NIL {
+                continue;
+            }
+            loop {
+                self.push_down(t);
+                match self.nodes[t].right {
+                    NIL => break,
+                    right => t = right,
+                }
+            }
+            tops.push_str(&self.labels[self.nodes[t].label]);
+        }
+        tops
+    }
+
+    fn collect(&mut self, t: usize, out: &mut Vec<String>) {
+        if t == NIL {
+            return;
+        }
+        self.push_down(t);
+        let (left, right) = (self.nodes[t].left, self.nodes[t].right);
+        self.collect(left, out);
+        out.push(self.labels[self.nodes[t].label].clone());
+        self.collect(right, out);
+    }
+
+    pub fn stacks(&mut self) -> Stacks {
+        let mut stacks = Vec::with_capacity(self.roots.len());
+        for s in 0..self.roots.len() {
+            let mut stack = Vec::with_capacity(self.size(self.roots[s]));
+            self.collect(self.roots[s], &mut stack);
+            stacks.push(stack);
+        }
+        Stacks { stacks }
+    }
+}
+
+/**
+run a program on the rope engine, returning the top crates
+*/
+pub fn run<C: Crane>(
+    crane: &C,
+    initial_state: &Stacks,
+    instructions: &[[usize; 3]],
+) -> Result<String, InstructionError> {
+    let mut rope = RopeStacks::new(initial_state);
+    for (i, &instruction) in instructions.iter().enumerate() {
+        rope.lift(crane, instruction)
+            .map_err(|error| InstructionError {
+                line: i + 1,
+                instruction,
+                error,
+                heights: rope.heights(),
+            })?;
+    }
+    Ok(rope.top_crates())
+}
+
+pub fn get_top_crates_fast<C: Crane>(filename: &str, crane: &C) -> Result<String, Box<dyn Error>> {
+    let (initial_state, instructions) = parse_input(read_file(filename)?)?;
+    Ok(run(crane, &initial_state, &instructions)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{move_crates_with, CappedCrane, CrateMover9000, CrateMover9001};
+    use super::*;
+
+    #[test]
+    fn test_get_top_crates_fast() {
+        assert_eq!(
+            get_top_crates_fast("input/day5.test", &CrateMover9000).unwrap(),
+            "CMZ"
+        );
+        assert_eq!(
+            get_top_crates_fast("input/day5.test", &CrateMover9001).unwrap(),
+            "MCD"
+        );
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        let state = Stacks::from_labels(&["AB", "C"]);
+        let err = run(&CrateMover9001, &state, &[[1, 1, 2], [3, 1, 2]]).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.heights, vec![1, 2]);
+    }
+
+    /**
+    random legal programs from a simple LCG
+    */
+    fn random_program(
+        seed: u64,
+        stacks: usize,
+        length: usize,
+        heights: &[usize],
+    ) -> Vec<[usize; 3]> {
+        let mut x = seed;
+        let mut next = move |m: usize| {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (x >> 33) as usize % m
+        };
+        let mut heights = heights.to_vec();
+        let mut program = Vec::new();
+        while program.len() < length {
+            let (from, to) = (next(stacks) + 1, next(stacks) + 1);
+            if heights[from - 1] == 0 {
+                continue;
+            }
+            let number = next(heights[from - 1]) + 1;
+            heights[from - 1] -= number;
+            heights[to - 1] += number;
+            program.push([number, from, to]);
+        }
+        program
+    }
+
+    fn assert_matches_vec_engine<C: Crane>(crane: &C) {
+        let state = Stacks::from_labels(&["ABCDEFG", "HIJ", "", "KLMNOPQRST", "U"]);
+        for seed in 0..20 {
+            let program = random_program(seed, 5, 200, &[7, 3, 0, 10, 1]);
+            let expected = move_crates_with(crane, state.clone(), program.clone()).unwrap();
+
+            let mut rope = RopeStacks::new(&state);
+            for &instruction in &program {
+                rope.lift(crane, instruction).unwrap();
+            }
+            assert_eq!(rope.top_crates(), expected.top_crates());
+            assert_eq!(rope.stacks(), expected);
+        }
+    }
+
+    /**
+    a crane only the tests know, turning over each pair of crates it lifts
+    */
+    struct PairFlipper;
+
+    impl Crane for PairFlipper {
+        fn lifts(&self, _number: usize) -> Lifts {
+            Lifts {
+                size: 2,
+                reversed: true,
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_vec_engine() {
+        assert_matches_vec_engine(&PairFlipper);
+        assert_matches_vec_engine(&CrateMover9000);
+        assert_matches_vec_engine(&CrateMover9001);
+        assert_matches_vec_engine(&CappedCrane::new(1).unwrap());
+        assert_matches_vec_engine(&CappedCrane::new(3).unwrap());
+    }
+
+    /**
+    stacks of crates with their own labels, all starting on the first stack
+    */
+    fn deep_stacks(crates: usize, stacks: usize) -> Stacks {
+        let mut state = Stacks {
+            stacks: vec![vec![]; stacks],
+        };
+        state.stacks[0] = (0..crates).map(|i| i.to_string()).collect();
+        state
+    }
+
+    fn assert_deep_matches_vec_engine<C: Crane>(crane: &C) {
+        let state = deep_stacks(20_000, 3);
+        let program = random_program(7, 3, 500, &[20_000, 0, 0]);
+        let expected = move_crates_with(crane, state.clone(), program.clone()).unwrap();
+        assert_eq!(run(crane, &state, &program).unwrap(), expected.top_crates());
+    }
+
+    #[test]
+    fn test_deep_stacks_match_vec_engine() {
+        assert_deep_matches_vec_engine(&CrateMover9000);
+        assert_deep_matches_vec_engine(&CrateMover9001);
+        assert_deep_matches_vec_engine(&CappedCrane::new(100).unwrap());
+    }
+
+    #[test]
+    fn test_deep_stacks() {
+        // a million uniquely labelled crates moved in huge runs
+        let state = deep_stacks(1_000_000, 3);
+        let mut rope = RopeStacks::new(&state);
+
+        // turned over onto the second stack, the bottom crate ends up on top
+        rope.lift(&CrateMover9000, [1_000_000, 1, 2]).unwrap();
+        assert_eq!(rope.top_crates(), "0");
+
+        // all but one carried in order to the third stack, leaving the old top
+        rope.lift(&CrateMover9001, [999_999, 2, 3]).unwrap();
+        assert_eq!(rope.top_crates(), "9999990");
+        assert_eq!(rope.heights(), vec![0, 1, 999_999]);
+
+        // half put back a thousand at a time keeps each thousand in order
+        let crane = CappedCrane::new(1000).unwrap();
+        rope.lift(&crane, [500_000, 3, 1]).unwrap();
+        assert_eq!(rope.top_crates(), "499000999999500000");
+    }
+}
@@ -0,0 +1,283 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::read_file;
+use super::validate::{first_error, InstructionError};
+use super::{move_crates_with, parse_input, Crane, CrateMover9001, Stacks};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    // there has to be at least one crane to run anything
+    NoCranes,
+    Illegal(InstructionError),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScheduleError::NoCranes => write!(f, "a schedule needs at least one crane"),
+            ScheduleError::Illegal(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+/**
+instructions assigned to cranes over parallel time steps
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub cranes: usize,
+    // steps[t] holds (crane, instruction index) pairs run together at time t
+    pub steps: Vec<Vec<(usize, usize)>>,
+}
+
+impl Schedule {
+    pub fn makespan(&self) -> usize {
+        self.steps.len()
+    }
+
+    /**
+    for each crane, the instruction it runs at every time step, if any
+    */
+    pub fn timeline(&self) -> Vec<Vec<Option<usize>>> {
+        let mut timeline = vec![vec![None; self.makespan()]; self.cranes];
+        for (t, step) in self.steps.iter().enumerate() {
+            for &(crane, instruction) in step {
+                timeline[crane][t] = Some(instruction);
+            }
+        }
+        timeline
+    }
+
+    /**
+    run the schedule a time step at a time and check it ends where running the
+    program in order does; a schedule that leaves out or repeats an instruction,
+    names a crane it doesn't have or makes an illegal move doesn't match
+
+    errors are for the program itself being illegal when run in order
+    */
+    pub fn verify<C: Crane>(
+        &self,
+        crane: &C,
+        initial_state: &Stacks,
+        instructions: &[[usize; 3]],
+    ) -> Result<bool, InstructionError> {
+        let expected = move_crates_with(crane, initial_state.clone(), instructions.to_vec())?;
+
+        let mut seen = vec![false; instructions.len()];
+        let mut order = Vec::with_capacity(instructions.len());
+        for step in &self.steps {
+            for &(c, i) in step {
+                if c >= self.cranes || i >= instructions.len() || seen[i] {
+                    return Ok(false);
+                }
+                seen[i] = true;
+                order.push(instructions[i]);
+            }
+        }
+        if order.len() != instructions.len() {
+            return Ok(false);
+        }
+
+        Ok(move_crates_with(crane, initial_state.clone(), order)
+            .is_ok_and(|state| state == expected))
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "makespan {}", self.makespan())?;
+        for (crane, line) in self.timeline().iter().enumerate() {
+            let cells = line
+                .iter()
+                .map(|i| match i {
+                    Some(i) => format!("{:>4}", i + 1),
+                    None => format!("{:>4}", "."),
+                })
+                .collect::<String>();
+            write!(f, "\ncrane {}:{}", crane + 1, cells)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+schedule a program over k cranes working in parallel
+
+instructions sharing a stack keep their program order and never share a time
+step, which is enough for the result to match running them one by one; each
+instruction goes in the first step after everything it depends on that still
+has a crane free
+
+the program is checked against the initial state first, so an illegal
+instruction is reported rather than scheduled
+*/
+pub fn schedule(
+    initial_state: &Stacks,
+    instructions: &[[usize; 3]],
+    cranes: usize,
+) -> Result<Schedule, ScheduleError> {
+    if cranes == 0 {
+        return Err(ScheduleError::NoCranes);
+    }
+    if let Some(error) = first_error(initial_state, instructions) {
+        return Err(ScheduleError::Illegal(error));
+    }
+
+    let mut steps: Vec<Vec<(usize, usize)>> = Vec::new();
+    // the step after the last one to touch each stack
+    let mut ready: Vec<usize> = Vec::new();
+
+    for (i, &[_, from, to]) in instructions.iter().enumerate() {
+        let needed = from.max(to);
+        if ready.len() < needed {
+            ready.resize(needed, 0);
+        }
+
+        let mut t = ready[from - 1].max(ready[to - 1]);
+        while steps.get(t).is_some_and(|s| s.len() >= cranes) {
+            t += 1;
+        }
+        if t == steps.len() {
+            steps.push(Vec::new());
+        }
+
+        let crane = steps[t].len();
+        steps[t].push((crane, i));
+        ready[from - 1] = t + 1;
+        ready[to - 1] = t + 1;
+    }
+
+    Ok(Schedule { cranes, steps })
+}
+
+/**
+schedule a puzzle file and check it against running it in order on the 9001
+*/
+pub fn schedule_file(filename: &str, cranes: usize) -> Result<Schedule, Box<dyn Error>> {
+    let (initial_state, instructions) = parse_input(read_file(filename)?)?;
+    let plan = schedule(&initial_state, &instructions, cranes)?;
+
+    if !plan.verify(&CrateMover9001, &initial_state, &instructions)? {
+        return Err("schedule does not match running the program in order".into());
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::validate::MoveError;
+    use super::super::CrateMover9000;
+    use super::*;
+
+    fn stacks() -> Stacks {
+        Stacks::from_labels(&["A", "B", "C", "D", "E", "F"])
+    }
+
+    #[test]
+    fn test_schedule_independent() {
+        let instructions = [[1, 1, 2], [1, 3, 4], [1, 5, 6]];
+        let plan = schedule(&stacks(), &instructions, 2).unwrap();
+        assert_eq!(plan.makespan(), 2);
+        assert_eq!(plan.steps, vec![vec![(0, 0), (1, 1)], vec![(0, 2)]]);
+        assert_eq!(schedule(&stacks(), &instructions, 3).unwrap().makespan(), 1);
+    }
+
+    #[test]
+    fn test_schedule_dependent() {
+        // each move shares a stack with the one before
+        let instructions = [[1, 1, 2], [1, 2, 3], [1, 3, 1]];
+        assert_eq!(schedule(&stacks(), &instructions, 3).unwrap().makespan(), 3);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let plan = schedule(&stacks(), &[[1, 1, 2], [1, 3, 4], [1, 2, 3]], 2).unwrap();
+        assert_eq!(
+            plan.timeline(),
+            vec![vec![Some(0), Some(2)], vec![Some(1), None]]
+        );
+        assert_eq!(
+            plan.to_string(),
+            "makespan 2\ncrane 1:   1   3\ncrane 2:   2   ."
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let state = Stacks::from_labels(&["ABC", "D", "EF", ""]);
+        let instructions = [[2, 1, 2], [1, 3, 4], [1, 2, 3], [1, 4, 1]];
+        let plan = schedule(&state, &instructions, 2).unwrap();
+        assert!(plan.verify(&CrateMover9001, &state, &instructions).unwrap());
+        assert!(plan.verify(&CrateMover9000, &state, &instructions).unwrap());
+        assert!(plan.verify(&CrateMover9000, &state, &[[9, 1, 2]]).is_err());
+    }
+
+    #[test]
+    fn test_verify_hand_built() {
+        let state = Stacks::from_labels(&["ABC", "D", "EF", ""]);
+        let instructions = [[2, 1, 2], [1, 3, 4], [1, 2, 3], [1, 4, 1]];
+        let verify = |cranes: usize, steps: Vec<Vec<(usize, usize)>>| {
+            Schedule { cranes, steps }
+                .verify(&CrateMover9001, &state, &instructions)
+                .unwrap()
+        };
+
+        assert!(verify(
+            1,
+            vec![vec![(0, 0)], vec![(0, 1)], vec![(0, 2)], vec![(0, 3)]]
+        ));
+        // an instruction that doesn't exist
+        assert!(!verify(1, vec![vec![(0, 0), (0, 7)]]));
+        // one run twice and another left out
+        assert!(!verify(
+            1,
+            vec![vec![(0, 0)], vec![(0, 1)], vec![(0, 1)], vec![(0, 3)]]
+        ));
+        // a crane beyond the ones there are
+        assert!(!verify(
+            1,
+            vec![vec![(0, 0), (1, 1)], vec![(0, 2)], vec![(0, 3)]]
+        ));
+        // moving from the empty fourth stack before anything lands there
+        assert!(!verify(
+            1,
+            vec![vec![(0, 3)], vec![(0, 0)], vec![(0, 1)], vec![(0, 2)]]
+        ));
+    }
+
+    #[test]
+    fn test_schedule_invalid() {
+        let err = match schedule(&stacks(), &[[1, 1, 2], [1, 0, 2]], 2) {
+            Err(ScheduleError::Illegal(err)) => err,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.error,
+            MoveError::NoSuchStack {
+                stack: 0,
+                stacks: 6
+            }
+        );
+        assert!(schedule(&stacks(), &[[2, 1, 2]], 2).is_err());
+    }
+
+    #[test]
+    fn test_schedule_no_cranes() {
+        assert_eq!(
+            schedule(&stacks(), &[[1, 1, 2]], 0),
+            Err(ScheduleError::NoCranes)
+        );
+    }
+
+    #[test]
+    fn test_schedule_file() {
+        let plan = schedule_file("input/day5.test", 2).unwrap();
+        // every move in the sample touches stack 1, so none can run together
+        assert_eq!(plan.makespan(), 4);
+    }
+}
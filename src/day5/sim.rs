@@ -0,0 +1,313 @@
+use std::error::Error;
+use std::io::Write;
+
+use super::super::read_file;
+use super::validate::{first_error, InstructionError};
+use super::{parse_input, Crane, Stacks};
+
+/**
+one applied instruction and the crates it carried
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    // counted from one, like the instruction lines
+    pub step: usize,
+    pub instruction: [usize; 3],
+    // the crates taken off the source stack, bottom first
+    pub lifted: Vec<String>,
+    // the same crates as they ended up on the target stack, bottom first
+    pub landed: Vec<String>,
+    pub top_crates: String,
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_list(labels: &[String]) -> String {
+    let items = labels
+        .iter()
+        .map(|l| json_string(l))
+        .collect::<Vec<String>>();
+    format!("[{}]", items.join(","))
+}
+
+impl Move {
+    /**
+    the move as a single line of JSON
+    */
+    pub fn to_json(&self) -> String {
+        let [number, from, to] = self.instruction;
+        format!(
+            "{{\"step\":{},\"number\":{},\"from\":{},\"to\":{},\"lifted\":{},\"landed\":{},\"top\":{}}}",
+            self.step,
+            number,
+            from,
+            to,
+            json_list(&self.lifted),
+            json_list(&self.landed),
+            json_string(&self.top_crates)
+        )
+    }
+}
+
+/**
+runs a crane program one instruction at a time, keeping every applied move so
+the program can be stepped back and forth
+*/
+pub struct Simulator<C: Crane> {
+    crane: C,
+    instructions: Vec<[usize; 3]>,
+    state: Stacks,
+    history: Vec<Move>,
+}
+
+impl<C: Crane> Simulator<C> {
+    /**
+    the program is dry run first, so stepping through it can't fail part way
+    */
+    pub fn new(
+        crane: C,
+        initial_state: Stacks,
+        instructions: Vec<[usize; 3]>,
+    ) -> Result<Self, InstructionError> {
+        if let Some(error) = first_error(&initial_state, &instructions) {
+            return Err(error);
+        }
+        Ok(Simulator {
+            crane,
+            instructions,
+            state: initial_state,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn state(&self) -> &Stacks {
+        &self.state
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /**
+    number of instructions applied so far
+    */
+    pub fn position(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position() == self.instructions.len()
+    }
+
+    /**
+    apply the next instruction, also used to redo one that was undone
+    */
+    pub fn step(&mut self) -> Option<&Move> {
+        let instruction = *self.instructions.get(self.position())?;
+        let [number, from, to] = instruction;
+
+        let source = &self.state.stacks[from - 1];
+        let lifted = source[source.len() - number..].to_vec();
+
+        self.crane.lift(&mut self.state.stacks, instruction);
+
+        let target = &self.state.stacks[to - 1];
+        let landed = target[target.len() - number..].to_vec();
+
+        self.history.push(Move {
+            step: self.position() + 1,
+            instruction,
+            lifted,
+            landed,
+            top_crates: self.state.top_crates(),
+        });
+        self.history.last()
+    }
+
+    /**
+    put the crates from the last move back where they were
+    */
+    pub fn undo(&mut self) -> Option<Move> {
+        let last = self.history.pop()?;
+        let [number, from, to] = last.instruction;
+
+        let target = &mut self.state.stacks[to - 1];
+        target.truncate(target.len() - number);
+        self.state.stacks[from - 1].extend(last.lifted.iter().cloned());
+
+        Some(last)
+    }
+
+    pub fn redo(&mut self) -> Option<&Move> {
+        self.step()
+    }
+
+    /**
+    step or undo until exactly `position` instructions have been applied
+    */
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.instructions.len());
+        while self.position() > position {
+            self.undo();
+        }
+        while self.position() < position {
+            self.step();
+        }
+    }
+
+    pub fn run(&mut self) -> &Stacks {
+        self.seek(self.instructions.len());
+        &self.state
+    }
+
+    /**
+    write the applied moves as JSON lines
+    */
+    pub fn write_trace<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for m in &self.history {
+            writeln!(writer, "{}", m.to_json())?;
+        }
+        Ok(())
+    }
+}
+
+/**
+the first step after which two crane models leave the stacks in different states
+*/
+pub fn first_divergence<A: Crane, B: Crane>(
+    a: A,
+    b: B,
+    initial_state: Stacks,
+    instructions: Vec<[usize; 3]>,
+) -> Result<Option<usize>, InstructionError> {
+    let mut sim_a = Simulator::new(a, initial_state.clone(), instructions.clone())?;
+    let mut sim_b = Simulator::new(b, initial_state, instructions)?;
+
+    while let (Some(_), Some(_)) = (sim_a.step(), sim_b.step()) {
+        if sim_a.state() != sim_b.state() {
+            return Ok(Some(sim_a.position()));
+        }
+    }
+    Ok(None)
+}
+
+pub fn get_trace<C: Crane>(filename: &str, crane: C) -> Result<Vec<String>, Box<dyn Error>> {
+    let (initial_state, instructions) = parse_input(read_file(filename)?)?;
+    let mut sim = Simulator::new(crane, initial_state, instructions)?;
+    sim.run();
+    Ok(sim.history().iter().map(|m| m.to_json()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CrateMover9000, CrateMover9001};
+    use super::*;
+
+    fn sample<C: Crane>(crane: C) -> Simulator<C> {
+        let (initial_state, instructions) =
+            parse_input(read_file("input/day5.test").unwrap()).unwrap();
+        Simulator::new(crane, initial_state, instructions).unwrap()
+    }
+
+    #[test]
+    fn test_step() {
+        let mut sim = sample(CrateMover9000);
+        let m = sim.step().unwrap().clone();
+        assert_eq!(m.step, 1);
+        assert_eq!(m.lifted, vec!["D"]);
+        assert_eq!(m.top_crates, "DCP");
+        assert_eq!(sim.position(), 1);
+    }
+
+    #[test]
+    fn test_run() {
+        let mut sim = sample(CrateMover9000);
+        assert_eq!(sim.run().top_crates(), "CMZ");
+        assert!(sim.is_finished());
+        assert!(sim.step().is_none());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut sim = sample(CrateMover9000);
+        let initial = sim.state().clone();
+        sim.run();
+        let last = sim.state().clone();
+
+        while sim.undo().is_some() {}
+        assert_eq!(sim.state(), &initial);
+
+        sim.seek(2);
+        assert_eq!(sim.state().top_crates(), "CZ");
+        while sim.redo().is_some() {}
+        assert_eq!(sim.state(), &last);
+    }
+
+    #[test]
+    fn test_undo_9001() {
+        let mut sim = sample(CrateMover9001);
+        let initial = sim.state().clone();
+        sim.seek(3);
+        sim.seek(0);
+        assert_eq!(sim.state(), &initial);
+    }
+
+    #[test]
+    fn test_trace() {
+        let trace = get_trace("input/day5.test", CrateMover9000).unwrap();
+        assert_eq!(trace.len(), 4);
+        assert_eq!(
+            trace[1],
+            r#"{"step":2,"number":3,"from":1,"to":3,"lifted":["Z","N","D"],"landed":["D","N","Z"],"top":"CZ"}"#
+        );
+    }
+
+    #[test]
+    fn test_write_trace() {
+        let mut sim = sample(CrateMover9001);
+        sim.seek(1);
+        let mut out = Vec::new();
+        sim.write_trace(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"step\":1,\"number\":1,\"from\":2,\"to\":1,\"lifted\":[\"D\"],\"landed\":[\"D\"],\"top\":\"DCP\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\"), r#""a\"b\\""#);
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let (initial_state, instructions) =
+            parse_input(read_file("input/day5.test").unwrap()).unwrap();
+        assert_eq!(
+            first_divergence(CrateMover9000, CrateMover9001, initial_state, instructions),
+            Ok(Some(2))
+        );
+    }
+
+    #[test]
+    fn test_new_rejects_illegal_program() {
+        let (initial_state, instructions) =
+            parse_input(read_file("input/day5_invalid.test").unwrap()).unwrap();
+        let err = Simulator::new(CrateMover9000, initial_state, instructions)
+            .err()
+            .unwrap();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,227 @@
+use std::error::Error;
+use std::fmt;
+
+use super::super::read_file;
+use super::{parse_input_numbered, Stacks};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    // stacks are counted from one
+    NoSuchStack {
+        stack: usize,
+        stacks: usize,
+    },
+    NotEnoughCrates {
+        stack: usize,
+        needed: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack { stack, stacks } => {
+                write!(f, "there is no stack {}, only 1 to {}", stack, stacks)
+            }
+            MoveError::NotEnoughCrates {
+                stack,
+                needed,
+                available,
+            } => write!(
+                f,
+                "stack {} has {} crates but {} are needed",
+                stack, available, needed
+            ),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+/**
+check an instruction can be carried out given the height of every stack
+*/
+pub fn check_move(heights: &[usize], instruction: [usize; 3]) -> Result<(), MoveError> {
+    check_move_by(heights.len(), |stack| heights[stack], instruction)
+}
+
+/**
+check an instruction against a number of stacks, looking up the height of a
+stack by its index from zero only when it is needed
+*/
+pub fn check_move_by<H: Fn(usize) -> usize>(
+    stacks: usize,
+    height: H,
+    [number, from, to]: [usize; 3],
+) -> Result<(), MoveError> {
+    for stack in [from, to] {
+        if stack == 0 || stack > stacks {
+            return Err(MoveError::NoSuchStack { stack, stacks });
+        }
+    }
+    let available = height(from - 1);
+    if available < number {
+        return Err(MoveError::NotEnoughCrates {
+            stack: from,
+            needed: number,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/**
+an illegal instruction, with the stack heights when it was reached
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionError {
+    // counted from one, within the program or within the file it was read from
+    pub line: usize,
+    pub instruction: [usize; 3],
+    pub error: MoveError,
+    pub heights: Vec<usize>,
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [number, from, to] = self.instruction;
+        write!(
+            f,
+            "line {}: move {} from {} to {}: {} (stack heights {:?})",
+            self.line, number, from, to, self.error, self.heights
+        )
+    }
+}
+
+impl Error for InstructionError {}
+
+/**
+dry run a program against the initial state, reporting every illegal
+instruction; illegal instructions are skipped so later ones are still checked
+
+only the heights are tracked, as every crane model moves the same number of
+crates
+*/
+pub fn validate_program(
+    initial_state: &Stacks,
+    instructions: &[[usize; 3]],
+) -> Vec<InstructionError> {
+    dry_run(initial_state, instructions).collect()
+}
+
+/**
+the first illegal instruction in a program, stopping the dry run there
+*/
+pub fn first_error(
+    initial_state: &Stacks,
+    instructions: &[[usize; 3]],
+) -> Option<InstructionError> {
+    dry_run(initial_state, instructions).next()
+}
+
+fn dry_run<'a>(
+    initial_state: &Stacks,
+    instructions: &'a [[usize; 3]],
+) -> impl Iterator<Item = InstructionError> + 'a {
+    let mut heights = initial_state
+        .stacks
+        .iter()
+        .map(|s| s.len())
+        .collect::<Vec<usize>>();
+
+    instructions
+        .iter()
+        .enumerate()
+        .filter_map(
+            move |(i, &instruction)| match check_move(&heights, instruction) {
+                Ok(()) => {
+                    let [number, from, to] = instruction;
+                    heights[from - 1] -= number;
+                    heights[to - 1] += number;
+                    None
+                }
+                Err(error) => Some(InstructionError {
+                    line: i + 1,
+                    instruction,
+                    error,
+                    heights: heights.clone(),
+                }),
+            },
+        )
+}
+
+/**
+validate a puzzle file, reporting errors against the file's own line numbers
+*/
+pub fn validate_file(filename: &str) -> Result<Vec<InstructionError>, Box<dyn Error>> {
+    let (initial_state, instructions, lines) = parse_input_numbered(read_file(filename)?)?;
+
+    let mut errors = validate_program(&initial_state, &instructions);
+    for error in errors.iter_mut() {
+        error.line = lines[error.line - 1];
+    }
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_move() {
+        assert_eq!(check_move(&[1, 2], [2, 2, 1]), Ok(()));
+        assert_eq!(
+            check_move(&[1, 2], [1, 0, 1]),
+            Err(MoveError::NoSuchStack {
+                stack: 0,
+                stacks: 2
+            })
+        );
+        assert_eq!(
+            check_move(&[1, 2], [1, 1, 3]),
+            Err(MoveError::NoSuchStack {
+                stack: 3,
+                stacks: 2
+            })
+        );
+        assert_eq!(
+            check_move(&[1, 2], [5, 1, 2]),
+            Err(MoveError::NotEnoughCrates {
+                stack: 1,
+                needed: 5,
+                available: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_program() {
+        let state = Stacks::from_labels(&["ABC", "D"]);
+        let errors = validate_program(&state, &[[2, 1, 2], [5, 1, 2], [1, 2, 4], [3, 2, 1]]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(errors[0].heights, vec![1, 3]);
+        assert_eq!(errors[1].line, 3);
+    }
+
+    #[test]
+    fn test_first_error() {
+        let state = Stacks::from_labels(&["ABC", "D"]);
+        let error = first_error(&state, &[[2, 1, 2], [5, 1, 2], [1, 2, 4]]).unwrap();
+        assert_eq!((error.line, error.heights), (2, vec![1, 3]));
+        assert_eq!(first_error(&state, &[[2, 1, 2], [3, 2, 1]]), None);
+    }
+
+    #[test]
+    fn test_validate_file() {
+        assert!(validate_file("input/day5.test").unwrap().is_empty());
+        let errors = validate_file("input/day5_invalid.test").unwrap();
+        let lines = errors.iter().map(|e| e.line).collect::<Vec<usize>>();
+        assert_eq!(lines, vec![7, 9]);
+        assert_eq!(
+            errors[0].to_string(),
+            "line 7: move 5 from 1 to 2: stack 1 has 3 crates but 5 are needed (stack heights [3, 2, 1])"
+        );
+    }
+}
@@ -1,33 +1,141 @@
-use substring::Substring;
+use std::collections::{HashMap, VecDeque};
+
+use predicate::{find_marker, AllDistinct};
+
+pub mod framer;
+pub mod markers;
+pub mod predicate;
+pub mod stream;
 
 /**
-search a string for a chunk of specified length with no duplicated letters
+the last `length` characters of a stream along with how often each appears in
+them, so sliding along by one character is constant work whatever the length
 */
-pub fn get_marker_pos(stream: &str, length: usize) -> usize {
-    let mut pos = 0;
+#[derive(Debug, Clone)]
+pub struct Window {
+    length: usize,
+    chars: VecDeque<char>,
+    // ascii is counted in an array, anything else in the map
+    ascii: [usize; 128],
+    other: HashMap<char, usize>,
+    // how many different characters are in the window
+    distinct: usize,
+}
 
-    for i in 0..stream.len() {
-        if i < length - 1 {
-            // cannot create a full chunk yet
-            continue;
+impl Window {
+    pub fn new(length: usize) -> Self {
+        Window {
+            length,
+            chars: VecDeque::with_capacity(length + 1),
+            ascii: [0; 128],
+            other: HashMap::new(),
+            distinct: 0,
         }
+    }
 
-        // check there are no duplicates in this chunk
-        if !contains_duplicates(stream.substring(i + 1 - length, i + 1)) {
-            pos = i;
-            break;
+    /**
+    add one to or take one from the count of a character, returning the new count
+    */
+    fn adjust(&mut self, c: char, add: bool) -> usize {
+        let count = if c.is_ascii() {
+            &mut self.ascii[c as usize]
+        } else {
+            self.other.entry(c).or_insert(0)
+        };
+        if add {
+            *count += 1;
+        } else {
+            *count -= 1;
         }
+        let count = *count;
+        match (add, count) {
+            (true, 1) => self.distinct += 1,
+            (false, 0) => {
+                self.distinct -= 1;
+                self.other.remove(&c);
+            }
+            _ => (),
+        }
+        count
+    }
+
+    /**
+    add a character to the end of the window, which may overfill it, returning
+    how often it now appears
+    */
+    pub fn add(&mut self, c: char) -> usize {
+        self.chars.push_back(c);
+        self.adjust(c, true)
     }
 
-    // return the character after this chunk
-    pos + 1
+    /**
+    take the oldest character from the window if it is overfull, returning it
+    and how often it still appears
+    */
+    pub fn evict(&mut self) -> Option<(char, usize)> {
+        if self.chars.len() <= self.length {
+            return None;
+        }
+        self.pop()
+    }
+
+    /**
+    take the oldest character from the window, returning it and how often it
+    still appears
+    */
+    pub fn pop(&mut self) -> Option<(char, usize)> {
+        let old = self.chars.pop_front()?;
+        Some((old, self.adjust(old, false)))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.chars.len() == self.length
+    }
+
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.distinct
+    }
+
+    pub fn count(&self, c: char) -> usize {
+        if c.is_ascii() {
+            self.ascii[c as usize]
+        } else {
+            self.other.get(&c).copied().unwrap_or(0)
+        }
+    }
+
+    /**
+    the characters in the window, oldest first
+    */
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars.iter().copied()
+    }
+}
+
+/**
+search a string for a chunk of specified length with no duplicated letters,
+returning the number of characters read up to the end of the first one
+
+positions count characters rather than bytes so multi-byte input is treated
+the same as ascii; use `char_indices` to turn one into a byte offset
+*/
+pub fn get_marker_pos(stream: &str, length: usize) -> Option<usize> {
+    find_marker(stream, length, AllDistinct)
 }
 
 /**
 get string of arbitrary length and check for duplicates
 */
 pub fn contains_duplicates(s: &str) -> bool {
-    let length = s.len();
+    let length = s.chars().count();
     let mut vec = s.chars().collect::<Vec<char>>();
 
     // dedupe
@@ -41,6 +149,106 @@ pub fn contains_duplicates(s: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Instant;
+
+    /**
+    the original search, checking every window from scratch; the characters
+    are split out once so each window is a slice rather than a rescan
+    */
+    fn get_marker_pos_naive(stream: &str, length: usize) -> Option<usize> {
+        let chars = stream.chars().collect::<Vec<char>>();
+        for i in length - 1..chars.len() {
+            let window = chars[i + 1 - length..=i].iter().collect::<String>();
+            if !contains_duplicates(&window) {
+                return Some(i + 1);
+            }
+        }
+        None
+    }
+
+    /**
+    a stream with no marker until its last character
+    */
+    fn long_stream(bytes: usize, length: usize) -> String {
+        let symbols = (0..128u8).map(char::from).collect::<Vec<char>>();
+        let mut stream = symbols[..length - 1]
+            .iter()
+            .cycle()
+            .take(bytes)
+            .collect::<String>();
+        stream.push(symbols[length - 1]);
+        stream
+    }
+
+    #[test]
+    fn test_get_marker_pos_long_stream() {
+        for length in [4, 14, 100] {
+            let stream = long_stream(100_000, length);
+            assert_eq!(get_marker_pos(&stream, length), Some(stream.len()));
+
+            let stream = long_stream(20_000, length);
+            assert_eq!(
+                get_marker_pos(&stream, length),
+                get_marker_pos_naive(&stream, length)
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_marker_pos_not_found() {
+        assert_eq!(get_marker_pos("aaaa", 2), None);
+        assert_eq!(get_marker_pos("abc", 4), None);
+        assert_eq!(get_marker_pos("", 1), None);
+    }
+
+    #[test]
+    fn test_get_marker_pos_zero_length() {
+        assert_eq!(get_marker_pos("abc", 0), Some(0));
+    }
+
+    #[test]
+    fn test_get_marker_pos_unicode() {
+        // positions are in characters, the marker ends on the fourth
+        assert_eq!(get_marker_pos("ééèàç", 3), Some(4));
+        assert_eq!(get_marker_pos("😀😀🙃😀🎉🚀", 3), Some(5));
+        // different characters sharing a leading byte are still different
+        assert_eq!(get_marker_pos("ñòó", 3), Some(3));
+        assert_eq!(get_marker_pos("ñòó", 3), get_marker_pos_naive("ñòó", 3));
+    }
+
+    #[test]
+    fn test_contains_duplicates_unicode() {
+        assert!(!contains_duplicates("éèê"));
+        assert!(contains_duplicates("éèé"));
+    }
+
+    /**
+    run with `cargo test --release bench_get_marker_pos -- --ignored --nocapture`
+    */
+    #[test]
+    #[ignore]
+    fn bench_get_marker_pos() {
+        for length in [4, 14, 100] {
+            for bytes in [100_000, 1_000_000, 4_000_000] {
+                let stream = long_stream(bytes, length);
+
+                let start = Instant::now();
+                let pos = get_marker_pos(&stream, length);
+                let sliding = start.elapsed();
+
+                let start = Instant::now();
+                let naive = get_marker_pos_naive(&stream, length);
+                let rescanning = start.elapsed();
+
+                assert_eq!(pos, Some(stream.len()));
+                assert_eq!(naive, pos);
+                println!(
+                    "k = {:3}, {:7} bytes: sliding window {:?}, rescanning {:?}",
+                    length, bytes, sliding, rescanning
+                );
+            }
+        }
+    }
 
     #[test]
     fn test_contains_duplicates() {
@@ -52,20 +260,35 @@ mod tests {
 
     #[test]
     fn test_get_marker_pos() {
-        assert_eq!(get_marker_pos("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), 7);
-        assert_eq!(get_marker_pos("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), 5);
-        assert_eq!(get_marker_pos("nppdvjthqldpwncqszvftbrmjlhg", 4), 6);
-        assert_eq!(get_marker_pos("nnlpdvjthqldpwncqszvftbrmjlhg", 4), 5);
-        assert_eq!(get_marker_pos("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), 10);
-        assert_eq!(get_marker_pos("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), 11);
+        assert_eq!(get_marker_pos("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), Some(7));
+        assert_eq!(get_marker_pos("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), Some(5));
+        assert_eq!(get_marker_pos("nppdvjthqldpwncqszvftbrmjlhg", 4), Some(6));
+        assert_eq!(get_marker_pos("nnlpdvjthqldpwncqszvftbrmjlhg", 4), Some(5));
+        assert_eq!(
+            get_marker_pos("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4),
+            Some(10)
+        );
+        assert_eq!(
+            get_marker_pos("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4),
+            Some(11)
+        );
     }
 
     #[test]
     fn test_get_message_start_marker_pos() {
-        assert_eq!(get_marker_pos("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19);
-        assert_eq!(get_marker_pos("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), 23);
-        assert_eq!(get_marker_pos("nppdvjthqldpwncqszvftbrmjlhg", 14), 23);
-        assert_eq!(get_marker_pos("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), 29);
-        assert_eq!(get_marker_pos("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), 26);
+        assert_eq!(
+            get_marker_pos("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14),
+            Some(19)
+        );
+        assert_eq!(get_marker_pos("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), Some(23));
+        assert_eq!(get_marker_pos("nppdvjthqldpwncqszvftbrmjlhg", 14), Some(23));
+        assert_eq!(
+            get_marker_pos("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14),
+            Some(29)
+        );
+        assert_eq!(
+            get_marker_pos("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14),
+            Some(26)
+        );
     }
 }
@@ -0,0 +1,345 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::read_to_string;
+
+use super::contains_duplicates;
+use super::markers::{all_markers, MarkerKind, MESSAGE_MARKER, PACKET_MARKER};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    // the stream ended before a marker closed the frame at this offset
+    Truncated {
+        kind: MarkerKind,
+        offset: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Truncated {
+                kind,
+                offset,
+                found,
+            } => write!(
+                f,
+                "{} frame at {} has no closing marker after {} payload characters",
+                kind, offset, found
+            ),
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+/**
+a marker and the payload after it, offsets counting characters from the start
+of the stream
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub kind: MarkerKind,
+    // where the marker starts
+    pub offset: usize,
+    pub payload: &'a str,
+    // false when the stream ran out before the next marker
+    pub complete: bool,
+}
+
+impl fmt::Display for Frame<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: {}", self.offset, self.kind, self.payload)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub packets: usize,
+    pub messages: usize,
+    pub payload_chars: usize,
+    // characters before the first marker
+    pub skipped_chars: usize,
+    pub truncated: usize,
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} packets, {} messages, {} payload characters, {} skipped, {} truncated",
+            self.packets, self.messages, self.payload_chars, self.skipped_chars, self.truncated
+        )
+    }
+}
+
+/**
+the byte offset of a character offset, or the end of the string past it
+*/
+fn byte_offset(s: &str, chars: usize) -> usize {
+    s.char_indices().nth(chars).map_or(s.len(), |(i, _)| i)
+}
+
+/**
+a marker found in the stream, with its start in bytes and characters
+*/
+#[derive(Debug, Clone, Copy)]
+struct Found {
+    kind: MarkerKind,
+    byte: usize,
+    offset: usize,
+}
+
+/**
+splits a stream into frames using start-of-packet and start-of-message markers
+as delimiters, each frame being a marker and everything up to the next one
+
+a run of fourteen distinct characters is a message marker, otherwise the first
+four distinct characters are a packet marker. anything before the first marker
+is skipped, and a marker at the very end only closes the frame before it. a
+stream that ends without a closing marker, including part way through one,
+leaves its last frame truncated: an error in strict mode, otherwise returned
+marked incomplete
+*/
+pub struct Framer<'a> {
+    stream: &'a str,
+    // the marker opening the next frame, none before the first is found
+    current: Option<Found>,
+    strict: bool,
+    stats: FrameStats,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Framer<'a> {
+    pub fn new(stream: &'a str, strict: bool) -> Self {
+        Framer {
+            stream,
+            current: None,
+            strict,
+            stats: FrameStats::default(),
+            started: false,
+            done: false,
+        }
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /**
+    the first marker lying wholly at or after a position in the stream
+    */
+    fn find_marker(&self, byte: usize, offset: usize) -> Option<Found> {
+        let rest = &self.stream[byte..];
+        let marker = all_markers(rest, &[PACKET_MARKER]).next()?;
+        let start = marker.position - PACKET_MARKER;
+        let start_byte = byte_offset(rest, start);
+
+        // the first packet marker is also where any message marker starts
+        let candidate = rest[start_byte..]
+            .chars()
+            .take(MESSAGE_MARKER)
+            .collect::<String>();
+        let kind =
+            if candidate.chars().count() == MESSAGE_MARKER && !contains_duplicates(&candidate) {
+                MarkerKind::StartOfMessage
+            } else {
+                MarkerKind::StartOfPacket
+            };
+
+        Some(Found {
+            kind,
+            byte: byte + start_byte,
+            offset: offset + start,
+        })
+    }
+}
+
+impl<'a> Iterator for Framer<'a> {
+    type Item = Result<Frame<'a>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            self.current = self.find_marker(0, 0);
+            self.stats.skipped_chars = match self.current {
+                Some(found) => found.offset,
+                None => self.stream.chars().count(),
+            };
+        }
+        let Some(opening) = self.current else {
+            self.done = true;
+            return None;
+        };
+
+        let payload_byte =
+            opening.byte + byte_offset(&self.stream[opening.byte..], opening.kind.length());
+        let payload_offset = opening.offset + opening.kind.length();
+        self.current = self.find_marker(payload_byte, payload_offset);
+
+        let payload_end = match self.current {
+            Some(closing) => closing.byte,
+            None => self.stream.len(),
+        };
+        // a marker at the very end only closes the frame before it
+        if self.current.is_none() && payload_byte == payload_end {
+            self.done = true;
+            return None;
+        }
+
+        let payload = &self.stream[payload_byte..payload_end];
+        let found = payload.chars().count();
+        let frame = Frame {
+            kind: opening.kind,
+            offset: opening.offset,
+            payload,
+            complete: self.current.is_some(),
+        };
+
+        if !frame.complete {
+            self.done = true;
+            self.stats.truncated += 1;
+            if self.strict {
+                return Some(Err(FrameError::Truncated {
+                    kind: frame.kind,
+                    offset: frame.offset,
+                    found,
+                }));
+            }
+        }
+        match frame.kind {
+            MarkerKind::StartOfPacket => self.stats.packets += 1,
+            MarkerKind::StartOfMessage => self.stats.messages += 1,
+        }
+        self.stats.payload_chars += found;
+        Some(Ok(frame))
+    }
+}
+
+/**
+frame a whole file leniently and report what was in it
+*/
+pub fn get_frame_stats(filename: &str) -> Result<FrameStats, Box<dyn Error>> {
+    let stream = read_to_string(filename)?;
+    let mut framer = Framer::new(stream.trim_end(), false);
+    for frame in framer.by_ref() {
+        frame?;
+    }
+    Ok(framer.stats().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // noise, then a packet, a message and a packet, closed by a final marker;
+    // each payload ends on a repeat of the next marker's first character so
+    // no marker straddles the two
+    const STREAM: &str = "qqqabccccdefghijklmnoppppxyzzzzabc";
+
+    fn frames(stream: &str, strict: bool) -> Result<Vec<Frame<'_>>, FrameError> {
+        Framer::new(stream, strict).collect()
+    }
+
+    #[test]
+    fn test_framer() {
+        let frames = frames(STREAM, true)
+            .unwrap()
+            .iter()
+            .map(|f| f.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            frames,
+            vec![
+                "2 start-of-packet: cc",
+                "8 start-of-message: pp",
+                "24 start-of-packet: zz"
+            ]
+        );
+
+        let mut framer = Framer::new(STREAM, true);
+        framer.by_ref().for_each(drop);
+        assert_eq!(
+            *framer.stats(),
+            FrameStats {
+                packets: 2,
+                messages: 1,
+                payload_chars: 6,
+                skipped_chars: 2,
+                truncated: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_framer_no_closing_marker() {
+        let stream = format!("{}qq", STREAM);
+        let result = frames(&stream, true);
+        assert_eq!(
+            result,
+            Err(FrameError::Truncated {
+                kind: MarkerKind::StartOfPacket,
+                offset: 30,
+                found: 2
+            })
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "start-of-packet frame at 30 has no closing marker after 2 payload characters"
+        );
+    }
+
+    #[test]
+    fn test_framer_mid_marker() {
+        // the stream stops three characters into what would be a marker
+        let stream = format!("{}zab", STREAM);
+        assert!(frames(&stream, true).is_err());
+
+        let mut framer = Framer::new(&stream, false);
+        let frames = framer
+            .by_ref()
+            .collect::<Result<Vec<Frame>, FrameError>>()
+            .unwrap();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[3].payload, "zab");
+        assert!(!frames[3].complete);
+        assert!(frames[..3].iter().all(|f| f.complete));
+        assert_eq!(framer.stats().truncated, 1);
+        assert_eq!(framer.stats().packets, 3);
+    }
+
+    #[test]
+    fn test_framer_no_markers() {
+        let mut framer = Framer::new("aabbaabb", true);
+        assert_eq!(framer.next(), None);
+        assert_eq!(framer.stats().skipped_chars, 8);
+    }
+
+    #[test]
+    fn test_framer_unicode() {
+        let frames = frames("éàèçççñ😀🎉", true).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].offset, 0);
+        assert_eq!(frames[0].payload, "ç");
+    }
+
+    #[test]
+    fn test_get_frame_stats() {
+        // every character is in a marker, a payload or skipped
+        let stats = get_frame_stats("input/day6.txt").unwrap();
+        let length = read_to_string("input/day6.txt").unwrap().trim_end().len();
+        let frames = stats.packets + stats.messages;
+        assert!(frames > 0);
+        let markers = 4 * stats.packets + 14 * stats.messages;
+        let closing = length - stats.skipped_chars - stats.payload_chars - markers;
+        // complete streams end on a closing marker, truncated ones don't
+        match stats.truncated {
+            0 => assert!(closing == 4 || closing == 14),
+            _ => assert_eq!(closing, 0),
+        }
+    }
+}
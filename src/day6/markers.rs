@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::Chars;
+
+// marker lengths for the start of a packet and of a message
+pub const PACKET_MARKER: usize = 4;
+pub const MESSAGE_MARKER: usize = 14;
+
+/**
+a window of `length` distinct characters ending `position` characters into
+the stream
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub length: usize,
+    pub position: usize,
+}
+
+/**
+every marker for a set of lengths, in order of position and then length
+
+rather than a window per length this keeps where each character was last seen,
+which gives the longest run of distinct characters ending at each position;
+every length that fits in that run is a marker there
+*/
+pub struct Markers<I: Iterator<Item = char>> {
+    chars: I,
+    // sorted with no zero lengths or repeats
+    lengths: Vec<usize>,
+    last_seen: HashMap<char, usize>,
+    // characters read and the index the current distinct run starts at
+    position: usize,
+    start: usize,
+    // lengths already reported at this position
+    reported: usize,
+}
+
+impl<I: Iterator<Item = char>> Markers<I> {
+    pub fn new(chars: I, lengths: &[usize]) -> Self {
+        let mut lengths = lengths
+            .iter()
+            .copied()
+            .filter(|&l| l > 0)
+            .collect::<Vec<usize>>();
+        lengths.sort_unstable();
+        lengths.dedup();
+
+        Markers {
+            chars,
+            reported: lengths.len(),
+            lengths,
+            last_seen: HashMap::new(),
+            position: 0,
+            start: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for Markers<I> {
+    type Item = Marker;
+
+    fn next(&mut self) -> Option<Marker> {
+        loop {
+            let run = self.position - self.start;
+            if let Some(&length) = self.lengths.get(self.reported) {
+                if length <= run {
+                    self.reported += 1;
+                    return Some(Marker {
+                        length,
+                        position: self.position,
+                    });
+                }
+            }
+
+            let c = self.chars.next()?;
+            // the run restarts just after the last time this character appeared
+            if let Some(&seen) = self.last_seen.get(&c) {
+                self.start = self.start.max(seen + 1);
+            }
+            self.last_seen.insert(c, self.position);
+            self.position += 1;
+            self.reported = 0;
+        }
+    }
+}
+
+/**
+every marker in a string for each of the given lengths, positions counting
+characters as in `get_marker_pos`
+*/
+pub fn all_markers<'a>(stream: &'a str, lengths: &[usize]) -> Markers<Chars<'a>> {
+    Markers::new(stream.chars(), lengths)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl MarkerKind {
+    pub fn length(&self) -> usize {
+        match self {
+            MarkerKind::StartOfPacket => PACKET_MARKER,
+            MarkerKind::StartOfMessage => MESSAGE_MARKER,
+        }
+    }
+}
+
+impl fmt::Display for MarkerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkerKind::StartOfPacket => write!(f, "start-of-packet"),
+            MarkerKind::StartOfMessage => write!(f, "start-of-message"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub kind: MarkerKind,
+    pub offset: usize,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.offset)
+    }
+}
+
+/**
+start-of-packet and start-of-message markers from one pass over the stream,
+reported at the offset of the character after them
+*/
+pub fn events(stream: &str) -> impl Iterator<Item = Event> + '_ {
+    all_markers(stream, &[PACKET_MARKER, MESSAGE_MARKER]).map(|marker| Event {
+        kind: match marker.length {
+            PACKET_MARKER => MarkerKind::StartOfPacket,
+            _ => MarkerKind::StartOfMessage,
+        },
+        offset: marker.position,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{contains_duplicates, get_marker_pos};
+    use super::*;
+    use substring::Substring;
+
+    const SAMPLES: [&str; 5] = [
+        "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        "bvwbjplbgvbhsrlpgdmjqwftvncz",
+        "nppdvjthqldpwncqszvftbrmjlhg",
+        "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+        "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+    ];
+
+    /**
+    every marker found by checking each window on its own
+    */
+    fn brute_force(stream: &str, lengths: &[usize]) -> Vec<Marker> {
+        let mut markers = Vec::new();
+        for position in 1..=stream.chars().count() {
+            for &length in lengths {
+                if length <= position
+                    && !contains_duplicates(stream.substring(position - length, position))
+                {
+                    markers.push(Marker { length, position });
+                }
+            }
+        }
+        markers
+    }
+
+    #[test]
+    fn test_all_markers_matches_brute_force() {
+        for stream in SAMPLES.iter().chain(&["ééèàçé😀🙃😀", "aaaa", ""]) {
+            assert_eq!(
+                all_markers(stream, &[3, 4, 14]).collect::<Vec<Marker>>(),
+                brute_force(stream, &[3, 4, 14])
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_markers() {
+        for stream in SAMPLES {
+            for length in [4, 14] {
+                let first = all_markers(stream, &[4, 14])
+                    .find(|m| m.length == length)
+                    .map(|m| m.position);
+                assert_eq!(first, get_marker_pos(stream, length));
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_markers_lengths() {
+        // unsorted and repeated lengths are tidied, zero is ignored
+        let markers = all_markers("abcab", &[3, 0, 2, 3]).collect::<Vec<Marker>>();
+        let found = markers
+            .iter()
+            .map(|m| (m.length, m.position))
+            .collect::<Vec<(usize, usize)>>();
+        assert_eq!(
+            found,
+            vec![(2, 2), (2, 3), (3, 3), (2, 4), (3, 4), (2, 5), (3, 5)]
+        );
+    }
+
+    #[test]
+    fn test_events() {
+        let events = events(SAMPLES[0]).collect::<Vec<Event>>();
+        assert_eq!(
+            events[0],
+            Event {
+                kind: MarkerKind::StartOfPacket,
+                offset: 7
+            }
+        );
+        let message = events
+            .iter()
+            .find(|e| e.kind == MarkerKind::StartOfMessage)
+            .unwrap();
+        assert_eq!(message.to_string(), "start-of-message at 19");
+        assert!(events.windows(2).all(|w| w[0].offset <= w[1].offset));
+    }
+}
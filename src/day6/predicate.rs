@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use super::Window;
+
+/**
+what a full window has to look like to count as a marker
+
+predicates are told every time the count of a character in the window changes,
+so those that keep their own state can stay constant work per character
+*/
+pub trait Predicate {
+    fn changed(&mut self, _c: char, _previous: usize, _count: usize) {}
+
+    fn matches(&self, window: &Window) -> bool;
+}
+
+/**
+no character appears twice, the puzzle's marker
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllDistinct;
+
+impl Predicate for AllDistinct {
+    fn matches(&self, window: &Window) -> bool {
+        window.distinct() == window.len()
+    }
+}
+
+/**
+at most this many characters are repeats of one earlier in the window
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtMostRepeats(pub usize);
+
+impl Predicate for AtMostRepeats {
+    fn matches(&self, window: &Window) -> bool {
+        window.len() - window.distinct() <= self.0
+    }
+}
+
+/**
+exactly this many different characters
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExactlyDistinct(pub usize);
+
+impl Predicate for ExactlyDistinct {
+    fn matches(&self, window: &Window) -> bool {
+        window.distinct() == self.0
+    }
+}
+
+/**
+the window holds the same characters as a pattern in any order
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiset {
+    target: HashMap<char, usize>,
+    // characters whose count in the window differs from the pattern
+    mismatched: usize,
+}
+
+impl Multiset {
+    pub fn new(pattern: &str) -> Self {
+        let mut target = HashMap::new();
+        for c in pattern.chars() {
+            *target.entry(c).or_insert(0) += 1;
+        }
+        Multiset {
+            mismatched: target.len(),
+            target,
+        }
+    }
+
+    /**
+    how many characters the pattern has, which is the window length it needs
+    */
+    pub fn len(&self) -> usize {
+        self.target.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.target.is_empty()
+    }
+}
+
+impl Predicate for Multiset {
+    fn changed(&mut self, c: char, previous: usize, count: usize) {
+        let wanted = self.target.get(&c).copied().unwrap_or(0);
+        match (previous == wanted, count == wanted) {
+            (true, false) => self.mismatched += 1,
+            (false, true) => self.mismatched -= 1,
+            _ => (),
+        }
+    }
+
+    fn matches(&self, _window: &Window) -> bool {
+        self.mismatched == 0
+    }
+}
+
+/**
+any closure over the window, checked afresh at every position
+*/
+impl<F: Fn(&Window) -> bool> Predicate for F {
+    fn matches(&self, window: &Window) -> bool {
+        self(window)
+    }
+}
+
+/**
+a sliding window of fixed length tested against a predicate as each character
+arrives
+*/
+#[derive(Debug, Clone)]
+pub struct Detector<P: Predicate> {
+    window: Window,
+    predicate: P,
+}
+
+impl<P: Predicate> Detector<P> {
+    pub fn new(length: usize, predicate: P) -> Self {
+        Detector {
+            window: Window::new(length),
+            predicate,
+        }
+    }
+
+    /**
+    slide a character into the window, returning whether it now matches
+    */
+    pub fn push(&mut self, c: char) -> bool {
+        let count = self.window.add(c);
+        self.predicate.changed(c, count - 1, count);
+        if let Some((old, count)) = self.window.evict() {
+            self.predicate.changed(old, count + 1, count);
+        }
+        self.is_match()
+    }
+
+    /**
+    empty the window, telling the predicate about every character that leaves
+    */
+    pub fn clear(&mut self) {
+        while let Some((old, count)) = self.window.pop() {
+            self.predicate.changed(old, count + 1, count);
+        }
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.window.is_full() && self.predicate.matches(&self.window)
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+}
+
+/**
+the number of characters read up to the end of the first window matching a
+predicate, or none if the stream runs out first
+*/
+pub fn find_marker<P: Predicate>(stream: &str, length: usize, predicate: P) -> Option<usize> {
+    let mut detector = Detector::new(length, predicate);
+    if detector.is_match() {
+        return Some(0);
+    }
+    for (i, c) in stream.chars().enumerate() {
+        if detector.push(c) {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+/**
+every position a window matching a predicate ends at
+*/
+pub fn find_all_markers<P: Predicate>(stream: &str, length: usize, predicate: P) -> Vec<usize> {
+    let mut detector = Detector::new(length, predicate);
+    stream
+        .chars()
+        .enumerate()
+        .filter(|&(_, c)| detector.push(c))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::get_marker_pos;
+    use super::*;
+
+    const SAMPLE: &str = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
+    #[test]
+    fn test_all_distinct() {
+        assert_eq!(find_marker(SAMPLE, 4, AllDistinct), Some(7));
+        assert_eq!(find_marker(SAMPLE, 14, AllDistinct), Some(19));
+        assert_eq!(find_marker("abc", 0, AllDistinct), Some(0));
+        assert_eq!(find_marker("aaa", 2, AllDistinct), None);
+    }
+
+    #[test]
+    fn test_at_most_repeats() {
+        assert_eq!(
+            find_marker(SAMPLE, 4, AtMostRepeats(0)),
+            get_marker_pos(SAMPLE, 4)
+        );
+        // "mjqj" has one repeat
+        assert_eq!(find_marker(SAMPLE, 4, AtMostRepeats(1)), Some(4));
+        assert_eq!(find_all_markers("aaaaab", 3, AtMostRepeats(1)), vec![6]);
+    }
+
+    #[test]
+    fn test_exactly_distinct() {
+        assert_eq!(find_marker("aabbbcab", 4, ExactlyDistinct(1)), None);
+        assert_eq!(find_marker("aabbbcab", 3, ExactlyDistinct(1)), Some(5));
+        assert_eq!(
+            find_all_markers("aabbbcab", 4, ExactlyDistinct(2)),
+            vec![4, 5, 6]
+        );
+        // counts characters not bytes
+        assert_eq!(find_marker("ééèè", 4, ExactlyDistinct(2)), Some(4));
+    }
+
+    #[test]
+    fn test_multiset() {
+        let pattern = Multiset::new("abca");
+        assert_eq!(pattern.len(), 4);
+        assert_eq!(
+            find_all_markers("xaabcabaacb", pattern.len(), pattern),
+            vec![5, 6, 8, 10, 11]
+        );
+        assert_eq!(find_marker("abcb", 4, Multiset::new("aabc")), None);
+    }
+
+    #[test]
+    fn test_closure() {
+        // a window that starts and ends with the same character
+        let bookends = |w: &Window| w.chars().next() == w.chars().last();
+        assert_eq!(find_all_markers("abcaxbcx", 4, bookends), vec![4, 8]);
+
+        let vowels = |w: &Window| "aeiou".chars().map(|c| w.count(c)).sum::<usize>() >= 2;
+        assert_eq!(find_marker("bcdaxxexo", 3, vowels), Some(9));
+    }
+}
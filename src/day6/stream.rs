@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+
+use super::predicate::{AllDistinct, Detector, Predicate};
+
+// bytes read from the source at a time
+const CAPACITY: usize = 4096;
+
+/**
+markers found while reading a stream of unknown length a chunk at a time, so
+memory stays fixed however long the source runs; a marker is a window matching
+the predicate, all distinct characters unless another is given
+
+each marker is reported as soon as its last character arrives, counted in
+characters from the start of the stream, and the search for the next one starts
+afresh after it so markers never share characters
+*/
+pub struct MarkerStream<R: Read, P: Predicate = AllDistinct> {
+    reader: R,
+    buffer: [u8; CAPACITY],
+    // the undecoded bytes are buffer[start..end]
+    start: usize,
+    end: usize,
+    detector: Detector<P>,
+    // characters read so far
+    position: usize,
+}
+
+impl<R: Read> MarkerStream<R> {
+    pub fn new(reader: R, length: usize) -> Self {
+        Self::with_predicate(reader, length, AllDistinct)
+    }
+}
+
+impl<R: Read, P: Predicate> MarkerStream<R, P> {
+    pub fn with_predicate(reader: R, length: usize, predicate: P) -> Self {
+        MarkerStream {
+            reader,
+            buffer: [0; CAPACITY],
+            start: 0,
+            end: 0,
+            detector: Detector::new(length, predicate),
+            position: 0,
+        }
+    }
+
+    /**
+    characters read from the source so far
+    */
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /**
+    decode the next character, reading more of the source when the buffer runs
+    out part way through one
+    */
+    fn next_char(&mut self) -> io::Result<Option<char>> {
+        loop {
+            let pending = &self.buffer[self.start..self.end];
+            if let Some(&first) = pending.first() {
+                let width = match first {
+                    0x00..=0x7f => 1,
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 0,
+                };
+                if width == 0 {
+                    return Err(io::Error::new(ErrorKind::InvalidData, "invalid utf-8"));
+                }
+                if pending.len() >= width {
+                    let c = std::str::from_utf8(&pending[..width])
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?
+                        .chars()
+                        .next()
+                        .unwrap();
+                    self.start += width;
+                    return Ok(Some(c));
+                }
+            }
+
+            // move what is left of a split character to the front and refill
+            self.buffer.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+            let read = match self.reader.read(&mut self.buffer[self.end..]) {
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if read == 0 {
+                return match self.end {
+                    0 => Ok(None),
+                    _ => Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "stream ends part way through a character",
+                    )),
+                };
+            }
+            self.end += read;
+        }
+    }
+}
+
+impl<R: Read, P: Predicate> Iterator for MarkerStream<R, P> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let c = match self.next_char() {
+                Ok(Some(c)) => c,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            self.position += 1;
+            if self.detector.push(c) {
+                self.detector.clear();
+                return Some(Ok(self.position));
+            }
+        }
+    }
+}
+
+/**
+read a file as a stream until the first marker
+*/
+pub fn get_first_marker(filename: &str, length: usize) -> Result<usize, Box<dyn Error>> {
+    let file = File::open(filename)?;
+    match MarkerStream::new(file, length).next() {
+        Some(pos) => Ok(pos?),
+        None => Err("no marker in stream".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::get_marker_pos;
+    use super::super::predicate::{ExactlyDistinct, Multiset};
+    use super::*;
+
+    /**
+    hands out one byte per read to split every multi-byte character
+    */
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_first_marker() {
+        assert_eq!(get_first_marker("input/day6.txt", 4).unwrap(), 1275);
+        assert_eq!(get_first_marker("input/day6.txt", 14).unwrap(), 3605);
+    }
+
+    #[test]
+    fn test_matches_get_marker_pos() {
+        let stream = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        for length in [4, 14] {
+            let mut markers = MarkerStream::new(stream.as_bytes(), length);
+            assert_eq!(
+                markers.next().map(|pos| pos.unwrap()),
+                get_marker_pos(stream, length)
+            );
+        }
+    }
+
+    #[test]
+    fn test_later_markers() {
+        let markers = MarkerStream::new("aabcdeefghhh".as_bytes(), 4)
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(markers, vec![5, 10]);
+    }
+
+    #[test]
+    fn test_other_predicates() {
+        let markers = MarkerStream::with_predicate("aabbbcab".as_bytes(), 3, ExactlyDistinct(1))
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(markers, vec![5]);
+
+        // the predicate's own counts start over along with the window
+        let markers = MarkerStream::with_predicate("abcabcacb".as_bytes(), 3, Multiset::new("abc"))
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(markers, vec![3, 6, 9]);
+    }
+
+    #[test]
+    fn test_split_characters() {
+        let stream = "ééèàç😀🙃🎉";
+        let markers = MarkerStream::new(Trickle(stream.as_bytes()), 3)
+            .collect::<io::Result<Vec<usize>>>()
+            .unwrap();
+        assert_eq!(markers, vec![4, 7]);
+    }
+
+    #[test]
+    fn test_unbounded_source() {
+        // a source that never ends, taking only the markers asked for
+        let tail = "abcd".repeat(1000);
+        let source = io::repeat(b'a').take(10_000).chain(tail.as_bytes());
+        let mut markers = MarkerStream::new(source, 4);
+        assert_eq!(markers.next().unwrap().unwrap(), 10_004);
+        assert_eq!(markers.next().unwrap().unwrap(), 10_008);
+        assert_eq!(markers.position(), 10_008);
+
+        let mut endless = MarkerStream::new("abc".as_bytes().chain(io::repeat(b'z')), 3);
+        assert_eq!(endless.next().unwrap().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut markers = MarkerStream::new(&[b'a', 0xff, b'b'][..], 2);
+        assert_eq!(
+            markers.next().unwrap().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        let mut markers = MarkerStream::new(&[b'a', b'b', b'a', 0xe2, 0x82][..], 4);
+        assert_eq!(
+            markers.next().unwrap().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
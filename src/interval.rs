@@ -0,0 +1,611 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use num_traits::PrimInt;
+
+/**
+closed range of integers `start..=end`, stored by its bounds rather than its members
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: PrimInt> Interval<T> {
+    /**
+    panics if start is after end, use `try_new` when the bounds are untrusted
+    */
+    pub fn new(start: T, end: T) -> Self {
+        Self::try_new(start, end).expect("interval start must not be after its end")
+    }
+
+    pub fn try_new(start: T, end: T) -> Option<Self> {
+        if start <= end {
+            Some(Interval { start, end })
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    /**
+    check if all of other lies within this interval
+    */
+    pub fn covers(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::try_new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    /**
+    number of integers in the interval, which can be more than T holds; only an
+    interval over every 128 bit integer is too long, and saturates
+    */
+    // an interval always holds its start, so there is no `is_empty` to go with this
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u128 {
+        // only a u128 can be out of range for i128, and then both bounds are positive
+        let width = match (self.start.to_i128(), self.end.to_i128()) {
+            (Some(start), Some(end)) => end.abs_diff(start),
+            _ => self.end.to_u128().unwrap() - self.start.to_u128().unwrap(),
+        };
+        width.saturating_add(1)
+    }
+
+    pub fn relation(&self, other: &Self) -> Relation {
+        if self == other {
+            return Relation::Equals;
+        }
+        match Self::forward_relation(self, other) {
+            Some(relation) => relation,
+            None => Self::forward_relation(other, self).unwrap().inverse(),
+        }
+    }
+
+    /**
+    the six relations where a comes first, or finishes first from the same start
+    */
+    fn forward_relation(a: &Self, b: &Self) -> Option<Relation> {
+        let relation = if a.end < b.start {
+            if a.end.checked_add(&T::one()) == Some(b.start) {
+                Relation::Meets
+            } else {
+                Relation::Before
+            }
+        } else if a.start < b.start && a.end < b.end {
+            Relation::Overlaps
+        } else if a.start == b.start && a.end < b.end {
+            Relation::Starts
+        } else if a.start > b.start && a.end < b.end {
+            Relation::During
+        } else if a.start > b.start && a.end == b.end {
+            Relation::Finishes
+        } else {
+            return None;
+        };
+        Some(relation)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/**
+Allen's thirteen relations between two intervals, read as "self <relation> other"
+
+intervals are treated as runs of whole sections, so 1-3 meets 4-6
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    pub const ALL: [Relation; 13] = [
+        Relation::Before,
+        Relation::Meets,
+        Relation::Overlaps,
+        Relation::Starts,
+        Relation::During,
+        Relation::Finishes,
+        Relation::Equals,
+        Relation::FinishedBy,
+        Relation::Contains,
+        Relation::StartedBy,
+        Relation::OverlappedBy,
+        Relation::MetBy,
+        Relation::After,
+    ];
+
+    pub fn inverse(self) -> Self {
+        Relation::ALL[12 - self as usize]
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Relation::Before => "before",
+            Relation::Meets => "meets",
+            Relation::Overlaps => "overlaps",
+            Relation::Starts => "starts",
+            Relation::During => "during",
+            Relation::Finishes => "finishes",
+            Relation::Equals => "equals",
+            Relation::FinishedBy => "finished-by",
+            Relation::Contains => "contains",
+            Relation::StartedBy => "started-by",
+            Relation::OverlappedBy => "overlapped-by",
+            Relation::MetBy => "met-by",
+            Relation::After => "after",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntervalError(String);
+
+impl fmt::Display for ParseIntervalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid interval '{}'", self.0)
+    }
+}
+
+impl Error for ParseIntervalError {}
+
+impl<T: PrimInt + FromStr> Interval<T> {
+    /**
+    read both bounds of the `a-b` form without checking their order
+    */
+    pub fn parse_bounds(s: &str) -> Result<(T, T), ParseIntervalError> {
+        let err = || ParseIntervalError(s.to_string());
+
+        // skip the first char so a leading minus sign isn't taken as the separator
+        let split = s.char_indices().skip(1).find(|&(_, c)| c == '-');
+        let (start, end) = match split {
+            Some((i, _)) => (&s[..i], &s[i + 1..]),
+            None => return Err(err()),
+        };
+
+        let start = start.trim().parse().map_err(|_| err())?;
+        let end = end.trim().parse().map_err(|_| err())?;
+        Ok((start, end))
+    }
+}
+
+impl<T: PrimInt + FromStr> FromStr for Interval<T> {
+    type Err = ParseIntervalError;
+
+    /**
+    parse the `a-b` form used by the section assignments
+    */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = Self::parse_bounds(s)?;
+        Self::try_new(start, end).ok_or_else(|| ParseIntervalError(s.to_string()))
+    }
+}
+
+/**
+sorted set of disjoint intervals, overlapping or adjacent members are merged
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet {
+            intervals: Vec::new(),
+        }
+    }
+
+    /**
+    sort and merge arbitrary intervals into canonical form
+    */
+    fn normalise(mut intervals: Vec<Interval<T>>) -> Self {
+        intervals.sort();
+
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                // touching counts too, 1-3 and 4-6 cover 1-6
+                Some(last)
+                    if last
+                        .end
+                        .checked_add(&T::one())
+                        .is_none_or(|next| interval.start <= next) =>
+                {
+                    last.end = last.end.max(interval.end);
+                }
+                _ => merged.push(interval),
+            }
+        }
+
+        IntervalSet { intervals: merged }
+    }
+
+    /**
+    add an interval, merging it with any members it overlaps or touches; the
+    members involved are found by binary search so only they are looked at
+    */
+    pub fn insert(&mut self, interval: Interval<T>) {
+        // members ending more than one short of the new start are left alone
+        let first = self.intervals.partition_point(|i| {
+            i.end
+                .checked_add(&T::one())
+                .is_some_and(|next| next < interval.start)
+        });
+        // as are members starting more than one past the new end
+        let last = self.intervals.partition_point(|i| {
+            interval
+                .end
+                .checked_add(&T::one())
+                .is_none_or(|next| i.start <= next)
+        });
+
+        let mut merged = interval;
+        if first < last {
+            merged.start = merged.start.min(self.intervals[first].start);
+            merged.end = merged.end.max(self.intervals[last - 1].end);
+        }
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Interval<T>> {
+        self.intervals.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /**
+    total number of integers covered, saturating like `Interval::len`
+    */
+    pub fn len(&self) -> u128 {
+        self.intervals
+            .iter()
+            .fold(0, |sum: u128, interval| sum.saturating_add(interval.len()))
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.intervals.partition_point(|i| i.end < value);
+        self.intervals
+            .get(idx)
+            .is_some_and(|interval| interval.contains(value))
+    }
+
+    /**
+    smallest interval holding every member, if there are any
+    */
+    pub fn span(&self) -> Option<Interval<T>> {
+        let first = self.intervals.first()?;
+        let last = self.intervals.last()?;
+        Some(Interval::new(first.start, last.end))
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut intervals = self.intervals.clone();
+        intervals.extend_from_slice(&other.intervals);
+        Self::normalise(intervals)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (&self.intervals[i], &other.intervals[j]);
+            if let Some(overlap) = a.intersection(b) {
+                intervals.push(overlap);
+            }
+            // drop whichever finishes first, it can't meet anything further on
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let mut j = 0;
+
+        for a in &self.intervals {
+            let mut start = a.start;
+            let mut remaining = true;
+
+            // skip anything in other that ends before this interval starts
+            while j < other.intervals.len() && other.intervals[j].end < a.start {
+                j += 1;
+            }
+
+            let mut k = j;
+            while remaining && k < other.intervals.len() && other.intervals[k].start <= a.end {
+                let b = &other.intervals[k];
+                if b.start > start {
+                    intervals.push(Interval::new(start, b.start - T::one()));
+                }
+                match b.end.checked_add(&T::one()) {
+                    Some(next) if b.end < a.end => start = next,
+                    _ => remaining = false,
+                }
+                k += 1;
+            }
+
+            if remaining {
+                intervals.push(Interval::new(start, a.end));
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    /**
+    the holes between members, from the first start to the last end
+    */
+    pub fn gaps(&self) -> Self {
+        match self.span() {
+            Some(span) => IntervalSet::from(span).difference(self),
+            None => IntervalSet::new(),
+        }
+    }
+}
+
+impl<T: PrimInt> From<Interval<T>> for IntervalSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        IntervalSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl<T: PrimInt> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        Self::normalise(iter.into_iter().collect())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = self
+            .intervals
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i32, i32)]) -> IntervalSet<i32> {
+        intervals
+            .iter()
+            .map(|&(a, b)| Interval::new(a, b))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval_set_merges() {
+        assert_eq!(
+            set(&[(4, 6), (1, 3), (8, 9), (9, 12)]),
+            set(&[(1, 6), (8, 12)])
+        );
+        assert_eq!(set(&[(1, 3), (4, 6)]).iter().count(), 1);
+    }
+
+    #[test]
+    fn test_interval_set_merges_at_max() {
+        let full = [Interval::new(250u8, 255), Interval::new(0, 249)]
+            .into_iter()
+            .collect::<IntervalSet<u8>>();
+        assert_eq!(full, IntervalSet::from(Interval::new(0, 255)));
+    }
+
+    #[test]
+    fn test_interval_set_insert() {
+        let mut s = set(&[(1, 2), (6, 8)]);
+        s.insert(Interval::new(3, 5));
+        assert_eq!(s, set(&[(1, 8)]));
+    }
+
+    #[test]
+    fn test_interval_set_insert_positions() {
+        let mut s = set(&[(10, 12), (20, 22), (30, 32)]);
+        s.insert(Interval::new(1, 2));
+        s.insert(Interval::new(40, 41));
+        s.insert(Interval::new(15, 16));
+        assert_eq!(
+            s,
+            set(&[(1, 2), (10, 12), (15, 16), (20, 22), (30, 32), (40, 41)])
+        );
+
+        // bridges the run from 15 to 32 and touches 40 without reaching 1
+        s.insert(Interval::new(14, 39));
+        assert_eq!(s, set(&[(1, 2), (10, 12), (14, 41)]));
+        s.insert(Interval::new(11, 11));
+        assert_eq!(s, set(&[(1, 2), (10, 12), (14, 41)]));
+        s.insert(Interval::new(3, 13));
+        assert_eq!(s, set(&[(1, 41)]));
+
+        let mut s = IntervalSet::from(Interval::new(0u8, 10));
+        s.insert(Interval::new(250, 255));
+        s.insert(Interval::new(11, 249));
+        assert_eq!(s, IntervalSet::from(Interval::new(0, 255)));
+    }
+
+    #[test]
+    fn test_interval_set_insert_matches_normalise() {
+        let mut x = 12345u64;
+        let mut s = IntervalSet::new();
+        let mut all = Vec::new();
+        for _ in 0..2000 {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let start = ((x >> 33) % 5000) as i32;
+            let interval = Interval::new(start, start + ((x >> 20) % 10) as i32);
+            s.insert(interval);
+            all.push(interval);
+        }
+        assert_eq!(s, all.into_iter().collect());
+    }
+
+    #[test]
+    fn test_interval_set_union() {
+        assert_eq!(
+            set(&[(1, 2)]).union(&set(&[(5, 6)])),
+            set(&[(1, 2), (5, 6)])
+        );
+    }
+
+    #[test]
+    fn test_interval_set_intersection() {
+        let a = set(&[(1, 5), (8, 12)]);
+        let b = set(&[(4, 9), (11, 20)]);
+        assert_eq!(a.intersection(&b), set(&[(4, 5), (8, 9), (11, 12)]));
+    }
+
+    #[test]
+    fn test_interval_set_difference() {
+        let a = set(&[(1, 10), (20, 30)]);
+        let b = set(&[(0, 2), (5, 6), (10, 22), (29, 40)]);
+        assert_eq!(a.difference(&b), set(&[(3, 4), (7, 9), (23, 28)]));
+        assert_eq!(b.difference(&b), IntervalSet::new());
+    }
+
+    #[test]
+    fn test_interval_set_len_and_gaps() {
+        let s = set(&[(1, 3), (7, 7), (10, 12)]);
+        assert_eq!(s.len(), 7);
+        assert_eq!(s.gaps(), set(&[(4, 6), (8, 9)]));
+        assert!(s.contains(7));
+        assert!(!s.contains(8));
+    }
+
+    #[test]
+    fn test_contains() {
+        let interval = Interval::new(2, 4);
+        assert!(interval.contains(2));
+        assert!(interval.contains(4));
+        assert!(!interval.contains(5));
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(Interval::new(2, 8).covers(&Interval::new(3, 7)));
+        assert!(!Interval::new(3, 7).covers(&Interval::new(2, 8)));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(Interval::new(5, 7).overlaps(&Interval::new(7, 9)));
+        assert!(!Interval::new(2, 4).overlaps(&Interval::new(6, 8)));
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(
+            Interval::new(2, 6).intersection(&Interval::new(4, 8)),
+            Some(Interval::new(4, 6))
+        );
+        assert_eq!(Interval::new(2, 3).intersection(&Interval::new(4, 5)), None);
+    }
+
+    #[test]
+    fn test_len() {
+        assert_eq!(Interval::new(6u8, 6).len(), 1);
+        assert_eq!(
+            Interval::new(1u64, 1_000_000_000_000).len(),
+            1_000_000_000_000
+        );
+        assert_eq!(Interval::new(i32::MIN, i32::MAX).len(), 1 << 32);
+        assert_eq!(Interval::new(0u8, 255).len(), 256);
+        assert_eq!(Interval::new(1, u128::MAX).len(), u128::MAX);
+        assert_eq!(Interval::new(i128::MIN, i128::MAX).len(), u128::MAX);
+        assert_eq!(set(&[(i32::MIN, -1), (1, i32::MAX)]).len(), (1 << 32) - 1);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("2-4".parse(), Ok(Interval::new(2, 4)));
+        assert_eq!("-3--1".parse(), Ok(Interval::new(-3, -1)));
+        assert!("4-2".parse::<Interval<i32>>().is_err());
+        assert!("300-400".parse::<Interval<u8>>().is_err());
+        assert!("24".parse::<Interval<i32>>().is_err());
+    }
+
+    #[test]
+    fn test_relation() {
+        let r = |a: (i32, i32), b: (i32, i32)| {
+            Interval::new(a.0, a.1).relation(&Interval::new(b.0, b.1))
+        };
+        assert_eq!(r((1, 2), (4, 5)), Relation::Before);
+        assert_eq!(r((1, 3), (4, 5)), Relation::Meets);
+        assert_eq!(r((1, 4), (4, 5)), Relation::Overlaps);
+        assert_eq!(r((4, 4), (4, 5)), Relation::Starts);
+        assert_eq!(r((3, 4), (2, 5)), Relation::During);
+        assert_eq!(r((3, 5), (2, 5)), Relation::Finishes);
+        assert_eq!(r((2, 5), (2, 5)), Relation::Equals);
+        assert_eq!(r((2, 5), (3, 5)), Relation::FinishedBy);
+        assert_eq!(r((2, 5), (3, 4)), Relation::Contains);
+        assert_eq!(r((4, 5), (4, 4)), Relation::StartedBy);
+        assert_eq!(r((4, 5), (1, 4)), Relation::OverlappedBy);
+        assert_eq!(r((4, 5), (1, 3)), Relation::MetBy);
+        assert_eq!(r((4, 5), (1, 2)), Relation::After);
+    }
+
+    #[test]
+    fn test_relation_inverse() {
+        for relation in Relation::ALL {
+            assert_eq!(relation.inverse().inverse(), relation);
+        }
+        assert_eq!(Relation::Before.inverse(), Relation::After);
+        assert_eq!(Relation::Equals.inverse(), Relation::Equals);
+    }
+
+    #[test]
+    fn test_relation_at_max() {
+        let a = Interval::new(0u8, 255);
+        assert_eq!(a.relation(&Interval::new(255, 255)), Relation::FinishedBy);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Interval::new(2, 4).to_string(), "2-4");
+    }
+}
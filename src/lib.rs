@@ -0,0 +1,21 @@
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod interval;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+
+type E = Box<dyn Error>;
+
+pub fn read_file(filename: &str) -> Result<Lines<BufReader<File>>, E> {
+    let file = File::open(filename)?;
+    Ok(BufReader::new(file).lines())
+}
@@ -1,24 +1,4 @@
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-
-use std::error::Error;
-use std::fs::read_to_string;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
-
-type E = Box<dyn Error>;
-
-pub fn read_file(filename: &str) -> Result<Lines<BufReader<File>>, E> {
-    let file = File::open(filename)?;
-    Ok(BufReader::new(file).lines())
-}
+use advent_of_code_2022::{day1, day2, day3, day4, day5, day6, day7, day8, day9};
 
 fn main() {
     let f_day1 = "input/day1.txt";
@@ -48,9 +28,14 @@ fn main() {
     println!("Day5.2: {}", day5::get_part2_top_crates(f_day5).unwrap());
 
     let f_day6 = "input/day6.txt";
-    let day6_input = read_to_string(f_day6).unwrap();
-    println!("Day6.1: {}", day6::get_marker_pos(&day6_input, 4));
-    println!("Day6.2: {}", day6::get_marker_pos(&day6_input, 14));
+    println!(
+        "Day6.1: {}",
+        day6::stream::get_first_marker(f_day6, 4).unwrap()
+    );
+    println!(
+        "Day6.2: {}",
+        day6::stream::get_first_marker(f_day6, 14).unwrap()
+    );
 
     let f_day7 = "input/day7.txt";
     println!(